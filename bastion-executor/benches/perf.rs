@@ -4,6 +4,8 @@ extern crate test;
 
 use bastion_executor::prelude::*;
 use lightproc::proc_stack::ProcStack;
+use std::thread;
+use std::time::{Duration, Instant};
 use test::{black_box, Bencher};
 
 #[bench]
@@ -23,3 +25,88 @@ fn increment(b: &mut Bencher) {
 
     black_box(sum);
 }
+
+#[bench]
+fn steal_from_overloaded_worker(b: &mut Bencher) {
+    b.iter(|| {
+        // Procs spawned from a worker land in its local queue, so the others have to steal them.
+        let handle = spawn(
+            async {
+                let handles: Vec<_> = (0..1_000)
+                    .map(|i| spawn(async move { black_box(i) }, ProcStack::default()))
+                    .collect();
+
+                for handle in handles {
+                    handle.await;
+                }
+            },
+            ProcStack::default(),
+        );
+
+        run(handle, ProcStack::default());
+    });
+}
+
+#[bench]
+fn spawn_latency(b: &mut Bencher) {
+    // Every spawn allocates a proc, which is recycled once the proc is destroyed.
+    b.iter(|| {
+        run(
+            spawn(async { black_box(1) }, ProcStack::default()),
+            ProcStack::default(),
+        )
+    });
+}
+
+#[bench]
+fn spawn_contention(b: &mut Bencher) {
+    // Threads spawning and destroying procs at the same time contend on the allocator.
+    b.iter(|| {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                thread::spawn(|| {
+                    let handles: Vec<_> = (0..250)
+                        .map(|i| spawn(async move { black_box(i) }, ProcStack::default()))
+                        .collect();
+
+                    for handle in handles {
+                        run(handle, ProcStack::default());
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    });
+}
+
+#[cfg(unix)]
+#[bench]
+fn idle_cpu_usage(b: &mut Bencher) {
+    // Make sure that the workers are started and idle.
+    run(spawn(async {}, ProcStack::default()), ProcStack::default());
+
+    let cpu_start = cpu_time();
+    let wall_start = Instant::now();
+
+    b.iter(|| thread::sleep(Duration::from_millis(1)));
+
+    let usage = (cpu_time() - cpu_start).as_secs_f64() / wall_start.elapsed().as_secs_f64();
+    assert!(
+        usage < 0.5,
+        "idle workers used {:.2}% of a core",
+        usage * 100.0
+    );
+}
+
+#[cfg(unix)]
+fn cpu_time() -> Duration {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) };
+
+    let to_duration =
+        |tv: libc::timeval| Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1_000);
+    to_duration(usage.ru_utime) + to_duration(usage.ru_stime)
+}
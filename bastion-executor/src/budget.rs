@@ -0,0 +1,191 @@
+//!
+//! Cooperative scheduling budget
+//!
+//! Every time a worker polls a proc, the proc gets a budget of cooperation points.
+//! Each [consume] spends a point, and once the budget is exhausted the proc is rescheduled
+//! to the back of the run queue instead of making progress. This way a proc whose future
+//! keeps being ready can't monopolise its worker.
+//!
+//! The budget also bounds the polls of a proc in a row on a worker, for the procs that never
+//! [consume]: a proc which wakes itself up after that many polls is rescheduled to the back of
+//! the run queue too.
+//!
+//! [yield_now] reschedules the proc to the back of the run queue unconditionally.
+//!
+//! Yielding procs are rescheduled to the global run queue, so that the procs waiting in the
+//! worker's local run queue go first.
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Default amount of cooperation points that a proc gets every time it is polled.
+pub(crate) const DEFAULT_POLL_BUDGET: usize = 128;
+
+thread_local! {
+    static BUDGET: Cell<Option<usize>> = const { Cell::new(None) };
+    static YIELDED: Cell<bool> = const { Cell::new(false) };
+    /// Pid of the proc polled last on this thread, and the number of its polls in a row.
+    static STREAK: Cell<(usize, usize)> = const { Cell::new((0, 0)) };
+}
+
+///
+/// Run the given function with the given budget while polling the proc with the given pid.
+///
+/// The proc is considered to have yielded from the start if it was polled more than the
+/// budget in a row.
+pub(crate) fn with_budget<F, R>(budget: usize, pid: usize, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    struct ResetBudget<'a>(&'a Cell<Option<usize>>);
+
+    impl Drop for ResetBudget<'_> {
+        fn drop(&mut self) {
+            self.0.set(None);
+            YIELDED.with(|yielded| yielded.set(false));
+        }
+    }
+
+    BUDGET.with(|current| {
+        current.set(Some(budget));
+        YIELDED.with(|yielded| yielded.set(over_budget(budget, pid)));
+        let _guard = ResetBudget(current);
+
+        f()
+    })
+}
+
+///
+/// Spends a cooperation point of the current proc.
+///
+/// Returns `Poll::Pending` and wakes the proc up again if the budget is exhausted,
+/// so the proc is rescheduled to the back of the run queue.
+/// Outside of the worker threads, budget is unlimited.
+pub fn poll_consume(cx: &mut Context<'_>) -> Poll<()> {
+    let exhausted = BUDGET.with(|current| match current.get() {
+        Some(0) => true,
+        Some(budget) => {
+            current.set(Some(budget - 1));
+            false
+        }
+        None => false,
+    });
+
+    if exhausted {
+        yield_proc(cx);
+        Poll::Pending
+    } else {
+        Poll::Ready(())
+    }
+}
+
+///
+/// Spends a cooperation point of the current proc, yielding if the budget is exhausted.
+///
+/// # Example
+/// ```rust
+/// use bastion_executor::budget;
+/// use bastion_executor::prelude::*;
+/// use lightproc::proc_stack::ProcStack;
+///
+/// let handle = spawn(
+///     async {
+///         let mut sum = 0;
+///         for i in 0..10_000 {
+///             // Let the other procs run from time to time.
+///             budget::consume().await;
+///             sum += i;
+///         }
+///         sum
+///     },
+///     ProcStack::default(),
+/// );
+///
+/// assert_eq!(run(handle, ProcStack::default()), Some(49_995_000));
+/// ```
+pub fn consume() -> Consume {
+    Consume(())
+}
+
+///
+/// Reschedules the current proc to the back of the run queue.
+///
+/// # Example
+/// ```rust
+/// use bastion_executor::prelude::*;
+/// use lightproc::proc_stack::ProcStack;
+///
+/// let handle = spawn(
+///     async {
+///         bastion_executor::yield_now().await;
+///         1 + 1
+///     },
+///     ProcStack::default(),
+/// );
+///
+/// assert_eq!(run(handle, ProcStack::default()), Some(2));
+/// ```
+pub fn yield_now() -> YieldNow {
+    YieldNow(false)
+}
+
+///
+/// Future returned by [consume].
+#[derive(Debug)]
+pub struct Consume(());
+
+impl Future for Consume {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        poll_consume(cx)
+    }
+}
+
+///
+/// Future returned by [yield_now].
+#[derive(Debug)]
+pub struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+
+        self.0 = true;
+        yield_proc(cx);
+        Poll::Pending
+    }
+}
+
+/// Counts a poll of the proc with the given pid, returns whether it was polled more than the
+/// budget in a row, starting a new streak if so.
+fn over_budget(budget: usize, pid: usize) -> bool {
+    STREAK.with(|streak| {
+        let (last, polls) = streak.get();
+        let polls = if last == pid { polls + 1 } else { 1 };
+
+        if polls > budget {
+            streak.set((pid, 0));
+            true
+        } else {
+            streak.set((pid, polls));
+            false
+        }
+    })
+}
+
+fn yield_proc(cx: &mut Context<'_>) {
+    YIELDED.with(|yielded| yielded.set(true));
+    cx.waker().wake_by_ref();
+}
+
+///
+/// Whether the proc that is being polled on this thread yielded.
+pub(crate) fn yielded() -> bool {
+    YIELDED.with(|yielded| yielded.get())
+}
@@ -0,0 +1,316 @@
+//!
+//! Executor configuration
+//!
+//! Configuration decides how many worker threads the executor starts, whether they are pinned
+//! to the cores and how they are named. It can be overridden from the environment with:
+//! * `BASTION_WORKERS` - Number of worker threads.
+//! * `BASTION_PINNING` - Enables (`1`, `true`, `on`) or disables (`0`, `false`, `off`) core pinning.
+//! * `BASTION_THREAD_NAME_PREFIX` - Prefix of the worker thread names.
+use crate::budget::DEFAULT_POLL_BUDGET;
+use std::env;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default prefix of the worker thread names.
+pub(crate) const DEFAULT_THREAD_NAME_PREFIX: &str = "bastion-async-thread";
+
+///
+/// Configuration of the executor's worker threads.
+///
+/// # Example
+/// ```rust
+/// use bastion_executor::config::ExecutorConfig;
+///
+/// let config = ExecutorConfig::new()
+///     .with_workers(2)
+///     .with_pinning(false)
+///     .with_thread_name_prefix("my-service")
+///     .on_thread_start(|| println!("Worker started"))
+///     .on_thread_stop(|| println!("Worker stopped"));
+/// ```
+#[derive(Clone)]
+pub struct ExecutorConfig {
+    /// Number of worker threads, one per allowed core within the CPU quota if not set.
+    pub(crate) workers: Option<usize>,
+    /// Whether the worker threads are pinned to cores.
+    pub(crate) pinning: bool,
+    /// Prefix of the worker thread names.
+    pub(crate) thread_name_prefix: String,
+    /// Callback executed at the start of every worker thread.
+    pub(crate) on_thread_start: Option<Arc<dyn Fn() + Send + Sync>>,
+    /// Callback executed when a worker thread stops.
+    pub(crate) on_thread_stop: Option<Arc<dyn Fn() + Send + Sync>>,
+    /// Cooperation points that a proc gets every time it is polled.
+    pub(crate) poll_budget: usize,
+    /// Duration of a single poll after which the watchdog reports the worker, if enabled.
+    pub(crate) watchdog: Option<Duration>,
+    /// Whether the watchdog moves the waiting procs of a stuck worker to the others.
+    pub(crate) watchdog_rebalance: bool,
+    /// Whether every worker only runs its own procs, without work stealing.
+    pub(crate) thread_per_core: bool,
+    /// Seed of the deterministic scheduling order, if enabled.
+    pub(crate) deterministic: Option<u64>,
+}
+
+impl ExecutorConfig {
+    ///
+    /// Creates a configuration with a pinned worker per core.
+    pub fn new() -> Self {
+        ExecutorConfig::default()
+    }
+
+    ///
+    /// Sets the number of worker threads.
+    ///
+    /// Workers are assigned to the cores in round-robin fashion.
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = Some(workers.max(1));
+        self
+    }
+
+    ///
+    /// Enables or disables pinning the worker threads to the cores.
+    pub fn with_pinning(mut self, pinning: bool) -> Self {
+        self.pinning = pinning;
+        self
+    }
+
+    ///
+    /// Sets the prefix of the worker thread names.
+    ///
+    /// Worker threads are named as `{prefix}-{worker index}`.
+    pub fn with_thread_name_prefix<T>(mut self, prefix: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.thread_name_prefix = prefix.into();
+        self
+    }
+
+    ///
+    /// Sets the cooperation points that a proc gets every time it is polled.
+    ///
+    /// Once a proc spends its budget with [consume], or wakes itself up after being polled
+    /// that many times in a row by a worker, it is rescheduled to the back of the run queue.
+    ///
+    /// [consume]: ../budget/fn.consume.html
+    pub fn with_poll_budget(mut self, budget: usize) -> Self {
+        self.poll_budget = budget;
+        self
+    }
+
+    ///
+    /// Enables the watchdog thread which reports the workers that are stuck polling a single
+    /// proc for longer than the given threshold.
+    ///
+    /// Reports are logged with the pid of the proc, and counted in the [stats].
+    ///
+    /// [stats]: ../stats/index.html
+    pub fn with_watchdog(mut self, threshold: Duration) -> Self {
+        self.watchdog = Some(threshold);
+        self
+    }
+
+    ///
+    /// Makes the watchdog move the procs waiting in the local run queue of a stuck worker
+    /// to the global run queue, so that the other workers can run them.
+    pub fn with_watchdog_rebalance(mut self, rebalance: bool) -> Self {
+        self.watchdog_rebalance = rebalance;
+        self
+    }
+
+    ///
+    /// Enables or disables the thread-per-core mode.
+    ///
+    /// In this mode, every proc is kept on a single worker for its whole life and the workers
+    /// never steal from each other. Procs spawned from a worker stay on that worker, procs
+    /// spawned from outside of the pool are distributed to the workers in round-robin fashion,
+    /// and [spawn_on] sends a proc to the worker of a given core.
+    ///
+    /// Every worker needs its own core, so there are never more workers than the cores.
+    /// Priorities of the procs aren't taken into account.
+    ///
+    /// [spawn_on]: ../pool/struct.Pool.html#method.spawn_on
+    pub fn with_thread_per_core(mut self, thread_per_core: bool) -> Self {
+        self.thread_per_core = thread_per_core;
+        self
+    }
+
+    ///
+    /// Makes the pool deterministic, for reproducible tests.
+    ///
+    /// A deterministic pool doesn't start any worker threads. Its procs are polled one at a time
+    /// by the thread driving the pool, in an order picked by the given seed, and they wait on
+    /// a virtual clock that is moved forward manually. See [deterministic] for driving the pool.
+    ///
+    /// [deterministic]: ../deterministic/index.html
+    pub fn with_deterministic(mut self, seed: u64) -> Self {
+        self.deterministic = Some(seed);
+        self
+    }
+
+    ///
+    /// Adds a callback that will be executed at the start of every worker thread.
+    pub fn on_thread_start<T>(mut self, callback: T) -> Self
+    where
+        T: Fn() + Send + Sync + 'static,
+    {
+        self.on_thread_start = Some(Arc::new(callback));
+        self
+    }
+
+    ///
+    /// Adds a callback that will be executed when a worker thread stops.
+    pub fn on_thread_stop<T>(mut self, callback: T) -> Self
+    where
+        T: Fn() + Send + Sync + 'static,
+    {
+        self.on_thread_stop = Some(Arc::new(callback));
+        self
+    }
+
+    ///
+    /// Applies the environment variable overrides on top of this configuration.
+    pub fn with_env_overrides(self) -> Self {
+        self.with_overrides(|key| env::var(key).ok())
+    }
+
+    /// Applies the overrides read with the given lookup of the environment variables.
+    fn with_overrides<L>(mut self, lookup: L) -> Self
+    where
+        L: Fn(&str) -> Option<String>,
+    {
+        if let Some(workers) =
+            lookup("BASTION_WORKERS").and_then(|w| w.trim().parse::<usize>().ok())
+        {
+            self = self.with_workers(workers);
+        }
+
+        if let Some(pinning) = lookup("BASTION_PINNING").and_then(|p| parse_bool(&p)) {
+            self.pinning = pinning;
+        }
+
+        if let Some(prefix) = lookup("BASTION_THREAD_NAME_PREFIX") {
+            self.thread_name_prefix = prefix;
+        }
+
+        self
+    }
+
+    ///
+    /// Number of worker threads that is going to be started, if it is set.
+    pub fn workers(&self) -> Option<usize> {
+        self.workers
+    }
+
+    ///
+    /// Whether the worker threads are pinned to the cores.
+    pub fn pinning(&self) -> bool {
+        self.pinning
+    }
+
+    ///
+    /// Prefix of the worker thread names.
+    pub fn thread_name_prefix(&self) -> &str {
+        &self.thread_name_prefix
+    }
+
+    ///
+    /// Cooperation points that a proc gets every time it is polled.
+    pub fn poll_budget(&self) -> usize {
+        self.poll_budget
+    }
+
+    ///
+    /// Threshold of the watchdog, if it is enabled.
+    pub fn watchdog(&self) -> Option<Duration> {
+        self.watchdog
+    }
+
+    ///
+    /// Whether the thread-per-core mode is enabled.
+    pub fn thread_per_core(&self) -> bool {
+        self.thread_per_core
+    }
+
+    ///
+    /// Seed of the deterministic scheduling order, if the pool is deterministic.
+    pub fn deterministic(&self) -> Option<u64> {
+        self.deterministic
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "on" | "yes" => Some(true),
+        "0" | "false" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> Self {
+        ExecutorConfig {
+            workers: None,
+            pinning: true,
+            thread_name_prefix: DEFAULT_THREAD_NAME_PREFIX.to_string(),
+            on_thread_start: None,
+            on_thread_stop: None,
+            poll_budget: DEFAULT_POLL_BUDGET,
+            watchdog: None,
+            watchdog_rebalance: false,
+            thread_per_core: false,
+            deterministic: None,
+        }
+    }
+}
+
+impl Debug for ExecutorConfig {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("ExecutorConfig")
+            .field("workers", &self.workers)
+            .field("pinning", &self.pinning)
+            .field("thread_name_prefix", &self.thread_name_prefix)
+            .field("poll_budget", &self.poll_budget)
+            .field("watchdog", &self.watchdog)
+            .field("watchdog_rebalance", &self.watchdog_rebalance)
+            .field("thread_per_core", &self.thread_per_core)
+            .field("deterministic", &self.deterministic)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_bool() {
+        assert_eq!(parse_bool("1"), Some(true));
+        assert_eq!(parse_bool(" On "), Some(true));
+        assert_eq!(parse_bool("false"), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let vars: HashMap<_, _> = vec![
+            ("BASTION_WORKERS", "3"),
+            ("BASTION_PINNING", "off"),
+            ("BASTION_THREAD_NAME_PREFIX", "overridden"),
+        ]
+        .into_iter()
+        .collect();
+
+        let config = ExecutorConfig::new()
+            .with_workers(8)
+            .with_thread_name_prefix("configured")
+            .with_overrides(|key| vars.get(key).map(|value| value.to_string()));
+
+        assert_eq!(config.workers(), Some(3));
+        assert!(!config.pinning());
+        assert_eq!(config.thread_name_prefix(), "overridden");
+    }
+}
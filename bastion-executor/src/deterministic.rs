@@ -0,0 +1,303 @@
+//!
+//! Deterministic single threaded executor for tests
+//!
+//! A pool started with [ExecutorConfig::with_deterministic] doesn't start any worker threads.
+//! Its procs are only polled by the thread that drives the pool with [run_until_stalled],
+//! [advance] or [block_on], one at a time, in an order picked by a pseudo random generator
+//! seeded with the given seed.
+//! Time doesn't pass on its own either: [sleep] waits on a virtual clock which is only moved
+//! forward with [advance]. The procs of the pool wait on it as well, for the backoff of their
+//! restarts and the timeouts of their handles.
+//!
+//! Running the same test with the same seed always interleaves the procs the same way,
+//! so a failing interleaving can be replayed by its seed.
+//!
+//! [ExecutorConfig::with_deterministic]: ../config/struct.ExecutorConfig.html#method.with_deterministic
+//! [run_until_stalled]: struct.Deterministic.html#method.run_until_stalled
+//! [advance]: struct.Deterministic.html#method.advance
+//! [block_on]: struct.Deterministic.html#method.block_on
+//! [sleep]: struct.Deterministic.html#method.sleep
+use crate::budget;
+use crate::worker;
+use lightproc::prelude::*;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::mem::{self, ManuallyDrop};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::time::Duration;
+
+///
+/// Scheduler and virtual clock of a deterministic pool.
+#[derive(Debug)]
+pub struct Deterministic {
+    seed: u64,
+    poll_budget: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    /// State of the pseudo random generator.
+    rng: u64,
+    /// Procs that are ready to be polled.
+    ready: Vec<LightProc>,
+    /// Time passed on the virtual clock.
+    now: Duration,
+    /// Wakers of the sleeping futures, by their deadline and registration order.
+    timers: BTreeMap<(Duration, u64), Waker>,
+    /// Registration order of the next timer.
+    next_timer: u64,
+}
+
+impl Deterministic {
+    pub(crate) fn new(seed: u64, poll_budget: usize) -> Self {
+        Deterministic {
+            seed,
+            poll_budget,
+            state: Mutex::new(State {
+                rng: seed,
+                ready: Vec::new(),
+                now: Duration::default(),
+                timers: BTreeMap::new(),
+                next_timer: 0,
+            }),
+        }
+    }
+
+    ///
+    /// Seed that the scheduling order is picked with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    ///
+    /// Time passed on the virtual clock.
+    pub fn now(&self) -> Duration {
+        self.state.lock().unwrap().now
+    }
+
+    pub(crate) fn schedule(&self, proc: LightProc) {
+        self.state.lock().unwrap().ready.push(proc);
+    }
+
+    pub(crate) fn is_idle(&self) -> bool {
+        self.state.lock().unwrap().ready.is_empty()
+    }
+
+    pub(crate) fn cancel_queued(&self) -> Vec<ProcStack> {
+        let ready = mem::take(&mut self.state.lock().unwrap().ready);
+
+        // Dropping the procs cancels them.
+        ready.into_iter().map(|proc| proc.stack().clone()).collect()
+    }
+
+    ///
+    /// Polls the ready procs, including the ones that become ready meanwhile, until none of
+    /// them is ready. Returns the number of polls.
+    pub fn run_until_stalled(&self) -> usize {
+        let mut polled = 0;
+
+        while let Some(proc) = self.next() {
+            budget::with_budget(self.poll_budget, proc.stack().get_pid(), || {
+                worker::set_stack(proc.stack(), || proc.run())
+            });
+            polled += 1;
+        }
+
+        polled
+    }
+
+    ///
+    /// Moves the virtual clock forward by the given duration.
+    ///
+    /// Sleeping futures are woken up in the order of their deadlines, and the procs are run
+    /// until stalled at every deadline, as if the time passed for real.
+    pub fn advance(&self, by: Duration) {
+        let until = self.now() + by;
+        self.run_until_stalled();
+
+        loop {
+            let wakers = {
+                let mut state = self.state.lock().unwrap();
+                let deadline = match state.timers.keys().next() {
+                    Some((deadline, _)) if *deadline <= until => *deadline,
+                    _ => break,
+                };
+
+                state.now = deadline;
+                let later = state.timers.split_off(&(deadline, u64::MAX));
+                mem::replace(&mut state.timers, later)
+            };
+
+            for (_, waker) in wakers {
+                waker.wake();
+            }
+            self.run_until_stalled();
+        }
+
+        self.state.lock().unwrap().now = until;
+    }
+
+    ///
+    /// Drives the pool until the given future completes.
+    ///
+    /// Returns `None` if the future can't complete, because all the procs are stalled and it
+    /// needs the virtual clock to be moved forward, or something from outside of the pool.
+    ///
+    /// # Example
+    /// ```rust
+    /// use bastion_executor::prelude::*;
+    /// use lightproc::proc_stack::ProcStack;
+    /// use std::time::Duration;
+    ///
+    /// let pool = Pool::builder()
+    ///     .with_name("deterministic-doc")
+    ///     .with_config(ExecutorConfig::new().with_deterministic(42))
+    ///     .build()
+    ///     .unwrap();
+    /// let scheduler = pool.deterministic().unwrap();
+    ///
+    /// let handle = pool.spawn(
+    ///     async move {
+    ///         scheduler.sleep(Duration::from_secs(60)).await;
+    ///         1 + 1
+    ///     },
+    ///     ProcStack::default(),
+    /// );
+    ///
+    /// // An hour passes in no time.
+    /// scheduler.advance(Duration::from_secs(3600));
+    /// assert_eq!(scheduler.block_on(handle), Some(Some(2)));
+    /// ```
+    pub fn block_on<F>(&self, future: F) -> Option<F::Output>
+    where
+        F: Future,
+    {
+        let woken = Arc::new(Woken(AtomicBool::new(true)));
+        let waker = waker_of(&woken);
+        let mut cx = Context::from_waker(&waker);
+
+        pin_utils::pin_mut!(future);
+
+        loop {
+            if woken.0.swap(false, Ordering::SeqCst) {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                    return Some(output);
+                }
+            }
+
+            if self.run_until_stalled() == 0 && !woken.0.load(Ordering::SeqCst) {
+                return None;
+            }
+        }
+    }
+
+    ///
+    /// Waits until the given duration passes on the virtual clock.
+    pub fn sleep(&self, duration: Duration) -> Sleep<'_> {
+        Sleep {
+            scheduler: self,
+            duration,
+            deadline: None,
+        }
+    }
+
+    /// Takes the next proc to poll out of the ready ones, picked by the seed.
+    fn next(&self) -> Option<LightProc> {
+        let mut state = self.state.lock().unwrap();
+        if state.ready.is_empty() {
+            return None;
+        }
+
+        let idx = (state.next_random() % state.ready.len() as u64) as usize;
+        Some(state.ready.swap_remove(idx))
+    }
+}
+
+impl State {
+    /// Next number of the splitmix64 generator.
+    fn next_random(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+///
+/// Future returned by [Deterministic::sleep].
+#[derive(Debug)]
+pub struct Sleep<'a> {
+    scheduler: &'a Deterministic,
+    duration: Duration,
+    deadline: Option<Duration>,
+}
+
+impl Future for Sleep<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.scheduler.state.lock().unwrap();
+        let deadline = match self.deadline {
+            Some(deadline) => deadline,
+            None => state.now + self.duration,
+        };
+
+        if state.now >= deadline {
+            return Poll::Ready(());
+        }
+
+        let order = state.next_timer;
+        state.next_timer += 1;
+        state.timers.insert((deadline, order), cx.waker().clone());
+        drop(state);
+
+        self.deadline = Some(deadline);
+        Poll::Pending
+    }
+}
+
+/// Virtual clock of a deterministic pool, which its procs wait on.
+pub(crate) struct VirtualClock(pub(crate) &'static Deterministic);
+
+impl Timer for VirtualClock {
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(self.0.sleep(duration))
+    }
+}
+
+/// Flag that is set when the future driven by `block_on` is woken up.
+struct Woken(AtomicBool);
+
+fn waker_of(woken: &Arc<Woken>) -> Waker {
+    let ptr = Arc::into_raw(woken.clone()) as *const ();
+    unsafe { Waker::from_raw(RawWaker::new(ptr, vtable())) }
+}
+
+fn vtable() -> &'static RawWakerVTable {
+    unsafe fn clone_raw(ptr: *const ()) -> RawWaker {
+        let arc = ManuallyDrop::new(Arc::from_raw(ptr as *const Woken));
+        mem::forget(Arc::clone(&arc));
+        RawWaker::new(ptr, vtable())
+    }
+
+    unsafe fn wake_raw(ptr: *const ()) {
+        let arc = Arc::from_raw(ptr as *const Woken);
+        arc.0.store(true, Ordering::SeqCst);
+    }
+
+    unsafe fn wake_by_ref_raw(ptr: *const ()) {
+        let arc = ManuallyDrop::new(Arc::from_raw(ptr as *const Woken));
+        arc.0.store(true, Ordering::SeqCst);
+    }
+
+    unsafe fn drop_raw(ptr: *const ()) {
+        drop(Arc::from_raw(ptr as *const Woken))
+    }
+
+    &RawWakerVTable::new(clone_raw, wake_raw, wake_by_ref_raw, drop_raw)
+}
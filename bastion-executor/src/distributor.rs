@@ -3,44 +3,142 @@
 //!
 //! Distributor provides a fair distribution of threads and pinning them to cores for fair execution.
 //! It assigns threads in round-robin fashion to all cores.
-use crate::placement::{self, CoreId};
+use crate::config::ExecutorConfig;
+use crate::placement::{self, CoreId, Locality};
+use crate::pool::Pool;
 use crate::run_queue::{Stealer, Worker};
 use crate::worker;
+use crossbeam_utils::sync::Parker;
 use lightproc::prelude::*;
+use std::panic::{self, AssertUnwindSafe};
 use std::thread;
 
 pub(crate) struct Distributor {
     pub(crate) cores: Vec<CoreId>,
+    pub(crate) config: ExecutorConfig,
+    pub(crate) workers: Vec<Worker<LightProc>>,
 }
 
 impl Distributor {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(config: ExecutorConfig) -> Self {
+        let available = placement::get_core_ids().expect("Core mapping couldn't be fetched");
+        // Without an explicit worker count, don't start more workers than the CPU quota
+        // allows to run at the same time.
+        let workers = config
+            .workers
+            .unwrap_or_else(|| match placement::get_cpu_quota() {
+                Some(quota) => quota.min(available.len()),
+                None => available.len(),
+            });
+
+        // Workers can't share a core in the thread-per-core mode.
+        let workers = if config.thread_per_core {
+            workers.min(available.len())
+        } else {
+            workers
+        };
+
+        // Assign workers to the cores in round-robin fashion.
+        let cores: Vec<CoreId> = available.iter().cycle().take(workers).cloned().collect();
+        let workers = cores.iter().map(|_| Worker::new_fifo()).collect();
+
         Distributor {
-            cores: placement::get_core_ids().expect("Core mapping couldn't be fetched"),
+            cores,
+            config,
+            workers,
         }
     }
 
-    pub(crate) fn assign(self) -> Vec<Stealer<LightProc>> {
-        let mut stealers = Vec::<Stealer<LightProc>>::new();
-
-        for core in self.cores {
-            let wrk = Worker::new_fifo();
-            stealers.push(wrk.stealer());
-
-            thread::Builder::new()
-                .name("bastion-async-thread".to_string())
-                .spawn(move || {
-                    // affinity assignment
-                    placement::set_for_current(core);
-
-                    // run initial stats generation for cores
-                    worker::stats_generator(core.id.clone(), &wrk);
-                    // actual execution
-                    worker::main_loop(core.id.clone(), wrk);
-                })
-                .expect("cannot start the thread for running proc");
+    pub(crate) fn stealers(&self) -> Vec<Stealer<LightProc>> {
+        self.workers.iter().map(|wrk| wrk.stealer()).collect()
+    }
+
+    ///
+    /// Locality of every worker's core to every other worker's core.
+    ///
+    /// Workers that aren't pinned can run on any core, so they are all equally close.
+    pub(crate) fn localities(&self) -> Vec<Vec<Locality>> {
+        let workers = self.cores.len();
+        if !self.config.pinning {
+            return vec![vec![Locality::SameNode; workers]; workers];
+        }
+
+        let topology = placement::get_topology();
+        self.cores
+            .iter()
+            .map(|from| {
+                self.cores
+                    .iter()
+                    .map(|to| topology.locality(*from, *to))
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub(crate) fn assign(self, pool: &'static Pool, parkers: Vec<Parker>) {
+        let workers = self.workers.into_iter().zip(parkers);
+        for (idx, (core, (wrk, parker))) in self.cores.into_iter().zip(workers).enumerate() {
+            spawn_worker(pool, self.config.clone(), idx, core, wrk, parker);
+        }
+    }
+}
+
+///
+/// Starts a worker thread of the pool that is going to be pinned to the given core.
+///
+/// If the worker thread goes down with a panic, its queue is handed to the pool for recovery.
+pub(crate) fn spawn_worker(
+    pool: &'static Pool,
+    config: ExecutorConfig,
+    idx: usize,
+    core: CoreId,
+    wrk: Worker<LightProc>,
+    parker: Parker,
+) {
+    let builder = thread::Builder::new().name(format!("{}-{}", config.thread_name_prefix, idx));
+    pool.spawn_thread(builder, move || {
+        // affinity assignment
+        if config.pinning {
+            placement::set_for_current(core);
         }
 
-        stealers
+        let hooks = ThreadHooks::start(&config);
+
+        // actual execution
+        let fallen = panic::catch_unwind(AssertUnwindSafe(|| {
+            worker::main_loop(pool, idx, wrk, &parker);
+        }))
+        .is_err();
+
+        drop(hooks);
+
+        if fallen {
+            // Queue of the fallen worker is left in the thread local storage.
+            let wrk = worker::take_local().expect("fallen worker lost its queue");
+            pool.recover_worker(config, idx, core, wrk, parker);
+        }
+    })
+    .expect("cannot start the thread for running proc");
+}
+
+/// Runs the thread start callback when created and the thread stop callback when dropped,
+/// even if the worker thread is going down with a panic.
+struct ThreadHooks<'a>(&'a ExecutorConfig);
+
+impl<'a> ThreadHooks<'a> {
+    fn start(config: &'a ExecutorConfig) -> Self {
+        if let Some(on_thread_start) = &config.on_thread_start {
+            (*on_thread_start.clone())();
+        }
+
+        ThreadHooks(config)
+    }
+}
+
+impl Drop for ThreadHooks<'_> {
+    fn drop(&mut self) {
+        if let Some(on_thread_stop) = &self.0.on_thread_stop {
+            (*on_thread_stop.clone())();
+        }
     }
 }
@@ -33,6 +33,9 @@
 mod macros;
 
 pub mod allocator;
+pub mod budget;
+pub mod config;
+pub mod deterministic;
 pub mod distributor;
 pub mod load_balancer;
 pub mod placement;
@@ -40,11 +43,16 @@ pub mod pool;
 pub mod run;
 pub mod run_queue;
 pub mod sleepers;
+pub mod stats;
+pub mod watchdog;
 pub mod worker;
 
+pub use crate::budget::yield_now;
+
 ///
 /// Prelude of Bastion Executor
 pub mod prelude {
+    pub use crate::config::*;
     pub use crate::pool::*;
     pub use crate::run::*;
 }
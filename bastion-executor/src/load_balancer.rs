@@ -1,16 +1,17 @@
 //!
 //! Module for gathering statistics about the run queues of the runtime
 //!
-//! Load balancer calculates sampled mean to provide average process execution amount
-//! to all runtime.
+//! Every worker publishes the size of its run queue to its own atomic counter.
+//! Counters are read lock-free and the mean level of the run queues is calculated on demand
+//! when a worker looks for procs to steal, so there is no sampling thread.
 //!
-use crate::load_balancer;
-use crate::placement;
-use crossbeam_utils::sync::ShardedLock;
-use fxhash::FxHashMap;
-use lazy_static::*;
-use std::thread;
-use std::time::Duration;
+//! Workers steal from the workers on the closest cores first: SMT siblings, then the cores on
+//! the same NUMA node, and the remote nodes last.
+//!
+use crate::placement::Locality;
+use crate::pool;
+use crossbeam_utils::CachePadded;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 ///
 /// Load-balancer struct which is just a convenience wrapper over the statistics calculations.
@@ -20,68 +21,128 @@ pub struct LoadBalancer;
 impl LoadBalancer {
     ///
     /// Statistics sampling thread for run queue load balancing.
-    pub fn sample() {
-        thread::Builder::new()
-            .name("load-balancer-thread".to_string())
-            .spawn(move || {
-                loop {
-                    if let Ok(mut stats) = load_balancer::stats().try_write() {
-                        // Write latest downscaled mean to statistics
-                        stats.mean_level = stats
-                            .smp_queues
-                            .values()
-                            .sum::<usize>()
-                            .wrapping_div(placement::get_core_ids().unwrap().len());
-                    }
-
-                    // Try sleeping for a while to wait
-                    thread::sleep(Duration::new(0, 10));
-                    // Yield immediately back to os so we can advance in workers
-                    thread::yield_now();
-                }
-            })
-            .expect("load-balancer couldn't start");
-    }
+    ///
+    /// Workers publish the sizes of their run queues on their own, this does nothing.
+    #[deprecated(
+        since = "0.3.1",
+        note = "workers publish the sizes of their run queues on their own"
+    )]
+    pub fn sample() {}
 }
 
 ///
 /// Holding all statistics related to the run queue
 ///
 /// Contains:
-/// * Global run queue size
-/// * Mean level of processes in the run queues
 /// * SMP queue distributions
-#[derive(Clone, Debug)]
+/// * Number of worker threads restarted after a panic
+/// * Localities of the workers' cores to each other
+#[derive(Debug)]
 pub struct Stats {
-    pub(crate) global_run_queue: usize,
-    pub(crate) mean_level: usize,
-    pub(crate) smp_queues: FxHashMap<usize, usize>,
+    pub(crate) smp_queues: Vec<CachePadded<AtomicUsize>>,
+    pub(crate) worker_restarts: AtomicUsize,
+    pub(crate) localities: Vec<Vec<Locality>>,
 }
 
-unsafe impl Send for Stats {}
-unsafe impl Sync for Stats {}
+impl Stats {
+    /// Creates the statistics of the workers, given the localities of every worker
+    /// to every other worker.
+    pub(crate) fn new(localities: Vec<Vec<Locality>>) -> Self {
+        Stats {
+            smp_queues: (0..localities.len())
+                .map(|_| CachePadded::new(AtomicUsize::new(0)))
+                .collect(),
+            worker_restarts: AtomicUsize::new(0),
+            localities,
+        }
+    }
+
+    pub(crate) fn set_queue_size(&self, affinity: usize, size: usize) {
+        if let Some(queue) = self.smp_queues.get(affinity) {
+            queue.store(size, Ordering::Relaxed);
+        }
+    }
+
+    ///
+    /// Last published run queue size of the given worker.
+    pub fn queue_size(&self, affinity: usize) -> Option<usize> {
+        self.smp_queues
+            .get(affinity)
+            .map(|queue| queue.load(Ordering::Relaxed))
+    }
+
+    ///
+    /// Mean level of processes in the run queues of the workers.
+    pub fn mean_level(&self) -> usize {
+        let workers = self.smp_queues.len().max(1);
+        self.smp_queues
+            .iter()
+            .map(|queue| queue.load(Ordering::Relaxed))
+            .sum::<usize>()
+            .wrapping_div(workers)
+    }
+
+    ///
+    /// Workers other than the given one, ordered from the closest to the farthest,
+    /// and from the most overloaded run queue to the least among the equally close ones.
+    pub(crate) fn steal_order(&self, affinity: usize) -> Vec<usize> {
+        let localities = self.localities.get(affinity);
+        let mut core_vec: Vec<(usize, Locality, usize)> = self
+            .smp_queues
+            .iter()
+            .map(|queue| queue.load(Ordering::Relaxed))
+            .enumerate()
+            .filter(|(core, _)| *core != affinity)
+            .map(|(core, size)| {
+                let locality = localities
+                    .and_then(|localities| localities.get(core))
+                    .cloned()
+                    .unwrap_or(Locality::SameNode);
+                (core, locality, size)
+            })
+            .collect();
+
+        // Closest first, then in descending order of the queue size
+        // so we can pick up from the most overloaded queue.
+        core_vec.sort_by(|x, y| x.1.cmp(&y.1).then(y.2.cmp(&x.2)));
+
+        core_vec.into_iter().map(|(core, _, _)| core).collect()
+    }
+
+    ///
+    /// Number of worker threads that went down with a panic and got replaced.
+    pub fn worker_restarts(&self) -> usize {
+        self.worker_restarts.load(Ordering::Relaxed)
+    }
+}
 
 ///
-/// Static access to runtime statistics
+/// Static access to runtime statistics of the default pool
 #[inline]
-pub fn stats() -> &'static ShardedLock<Stats> {
-    lazy_static! {
-        static ref LB_STATS: ShardedLock<Stats> = {
-            let stats = Stats {
-                global_run_queue: 0,
-                mean_level: 0,
-                smp_queues: FxHashMap::with_capacity_and_hasher(
-                    placement::get_core_ids().unwrap().len(),
-                    Default::default()
-                )
-            };
-
-            // Start sampler
-            LoadBalancer::sample();
-
-            // Return stats
-            ShardedLock::new(stats)
-        };
+pub fn stats() -> &'static Stats {
+    &pool::get().stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::placement::Locality::*;
+
+    #[test]
+    fn steal_order_prefers_close_workers() {
+        let stats = Stats::new(vec![
+            vec![SameCore, Sibling, SameNode, SameNode, Remote],
+            vec![Sibling, SameCore, SameNode, SameNode, Remote],
+            vec![SameNode, SameNode, SameCore, Sibling, Remote],
+            vec![SameNode, SameNode, Sibling, SameCore, Remote],
+            vec![Remote, Remote, Remote, Remote, SameCore],
+        ]);
+
+        for (worker, size) in [0, 1, 2, 8, 16].iter().enumerate() {
+            stats.set_queue_size(worker, *size);
+        }
+
+        // Sibling first, then the most loaded worker on the same node, remote one last.
+        assert_eq!(stats.steal_order(0), vec![1, 3, 2, 4]);
     }
-    &*LB_STATS
 }
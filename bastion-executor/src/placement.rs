@@ -3,13 +3,31 @@
 //!
 //! Placement module enables thread placement onto the cores.
 //! CPU level affinity assignment is done here.
+//!
+//! On Linux, only the cores in the CPU affinity mask of the process are used,
+//! and the CPU quota of the process' cgroup is respected while sizing the workers.
+//! SMT siblings and NUMA nodes of the cores are read from sysfs, so that the workers can
+//! prefer stealing from the cores close to them.
+use fxhash::FxHashMap;
+
+pub use lightproc::proc_stack::CoreId;
 
 /// This function tries to retrieve information
 /// on all the "cores" active on this system.
+///
+/// Cores that the process isn't allowed to run on aren't included.
 pub fn get_core_ids() -> Option<Vec<CoreId>> {
     get_core_ids_helper()
 }
 
+///
+/// Number of cores that the process can fully use according to its cgroup CPU quota.
+///
+/// Returns `None` if there is no quota, or it can't be read.
+pub fn get_cpu_quota() -> Option<usize> {
+    get_cpu_quota_helper()
+}
+
 ///
 /// Sets the current threads affinity
 pub fn set_for_current(core_id: CoreId) {
@@ -17,11 +35,66 @@ pub fn set_for_current(core_id: CoreId) {
 }
 
 ///
-/// CoreID implementation to identify system cores.
-#[derive(Copy, Clone, Debug)]
-pub struct CoreId {
-    /// Used core ID
-    pub id: usize,
+/// Reads the SMT and NUMA topology of the cores.
+///
+/// Cores without topology information are treated as unrelated cores on the same node.
+pub fn get_topology() -> Topology {
+    get_topology_helper()
+}
+
+///
+/// How close two cores are to each other, ordered from the closest to the farthest.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Locality {
+    /// Both are the same core.
+    SameCore,
+    /// Cores are SMT siblings, sharing a physical core and its caches.
+    Sibling,
+    /// Cores are on the same NUMA node.
+    SameNode,
+    /// Cores are on different NUMA nodes.
+    Remote,
+}
+
+///
+/// SMT and NUMA topology of the system's cores.
+#[derive(Clone, Debug, Default)]
+pub struct Topology {
+    /// SMT siblings of the cores, including the core itself.
+    siblings: FxHashMap<usize, Vec<usize>>,
+    /// NUMA node of the cores.
+    nodes: FxHashMap<usize, usize>,
+}
+
+impl Topology {
+    ///
+    /// NUMA node of the given core, if known.
+    pub fn node_of(&self, core: CoreId) -> Option<usize> {
+        self.nodes.get(&core.id).cloned()
+    }
+
+    ///
+    /// Whether the given cores are SMT siblings.
+    pub fn are_siblings(&self, a: CoreId, b: CoreId) -> bool {
+        self.siblings
+            .get(&a.id)
+            .is_some_and(|siblings| siblings.contains(&b.id))
+    }
+
+    ///
+    /// How close the given cores are to each other.
+    pub fn locality(&self, a: CoreId, b: CoreId) -> Locality {
+        if a.id == b.id {
+            Locality::SameCore
+        } else if self.are_siblings(a, b) {
+            Locality::Sibling
+        } else {
+            match (self.node_of(a), self.node_of(b)) {
+                (Some(a), Some(b)) if a != b => Locality::Remote,
+                _ => Locality::SameNode,
+            }
+        }
+    }
 }
 
 // Linux Section
@@ -38,16 +111,35 @@ fn set_for_current_helper(core_id: CoreId) {
     linux::set_for_current(core_id);
 }
 
+#[cfg(target_os = "linux")]
+#[inline]
+fn get_cpu_quota_helper() -> Option<usize> {
+    linux::get_cpu_quota()
+}
+
+#[cfg(target_os = "linux")]
+#[inline]
+fn get_topology_helper() -> Topology {
+    linux::get_topology()
+}
+
 #[cfg(target_os = "linux")]
 mod linux {
+    use std::fs;
     use std::mem;
+    use std::path::{Path, PathBuf};
 
-    use libc::{cpu_set_t, sched_getaffinity, sched_setaffinity, CPU_ISSET, CPU_SET, CPU_SETSIZE};
+    use libc::{
+        cpu_set_t, getpid, pid_t, sched_getaffinity, sched_setaffinity, CPU_ISSET, CPU_SET,
+        CPU_SETSIZE,
+    };
 
-    use super::CoreId;
+    use super::{CoreId, Topology};
 
     pub fn get_core_ids() -> Option<Vec<CoreId>> {
-        if let Some(full_set) = get_affinity_mask() {
+        // Mask of the process rather than the current thread,
+        // which might be pinned already.
+        if let Some(full_set) = get_process_affinity_mask() {
             let mut core_ids: Vec<CoreId> = Vec::new();
 
             for i in 0..CPU_SETSIZE as usize {
@@ -79,17 +171,202 @@ mod linux {
         }
     }
 
+    pub fn get_cpu_quota() -> Option<usize> {
+        get_cpu_quota_from(Path::new("/"))
+    }
+
+    /// Reads the CPU quota of the process from the cgroup files under the given root,
+    /// so that it can be tested with fixtures.
+    fn get_cpu_quota_from(root: &Path) -> Option<usize> {
+        let cgroups = fs::read_to_string(root.join("proc/self/cgroup")).ok()?;
+
+        cgroups
+            .lines()
+            .filter_map(|line| {
+                // Lines are formatted as `hierarchy-ID:controller-list:cgroup-path`.
+                let mut fields = line.splitn(3, ':');
+                let hierarchy = fields.next()?;
+                let controllers = fields.next()?;
+                let path = fields.next()?.trim_start_matches('/');
+
+                if hierarchy == "0" && controllers.is_empty() {
+                    // cgroup v2
+                    cgroup_dirs(root, "sys/fs/cgroup", path)
+                        .iter()
+                        .filter_map(|dir| read_cpu_max(dir))
+                        .min()
+                } else if controllers.split(',').any(|c| c == "cpu") {
+                    // cgroup v1
+                    ["sys/fs/cgroup/cpu", "sys/fs/cgroup/cpu,cpuacct"]
+                        .iter()
+                        .flat_map(|mount| cgroup_dirs(root, mount, path))
+                        .filter_map(|dir| read_cfs_quota(&dir))
+                        .min()
+                } else {
+                    None
+                }
+            })
+            .min()
+    }
+
+    /// Directories of the cgroup and of its ancestors, up to the mount point.
+    ///
+    /// The quota of every ancestor limits the cgroup too. Inside of a cgroup namespace,
+    /// e.g. in a container, the cgroup is mounted at the root.
+    fn cgroup_dirs(root: &Path, mount: &str, path: &str) -> Vec<PathBuf> {
+        let mount = root.join(mount);
+
+        mount
+            .join(path)
+            .ancestors()
+            .take_while(|dir| dir.starts_with(&mount))
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    /// Reads `cpu.max` of cgroup v2, formatted as `$MAX $PERIOD`.
+    fn read_cpu_max(dir: &Path) -> Option<usize> {
+        let cpu_max = fs::read_to_string(dir.join("cpu.max")).ok()?;
+        let mut fields = cpu_max.split_whitespace();
+        let quota = fields.next()?.parse::<u64>().ok()?;
+        let period = fields.next()?.parse::<u64>().ok()?;
+
+        cores_of(quota, period)
+    }
+
+    /// Reads `cpu.cfs_quota_us` and `cpu.cfs_period_us` of cgroup v1.
+    fn read_cfs_quota(dir: &Path) -> Option<usize> {
+        let read = |file: &str| {
+            fs::read_to_string(dir.join(file))
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+        };
+
+        let quota = read("cpu.cfs_quota_us")?;
+        let period = read("cpu.cfs_period_us")?;
+        if quota <= 0 || period <= 0 {
+            return None;
+        }
+
+        cores_of(quota as u64, period as u64)
+    }
+
+    /// Cores needed to use up the quota, at least one.
+    fn cores_of(quota: u64, period: u64) -> Option<usize> {
+        if period == 0 {
+            return None;
+        }
+
+        let cores = quota.div_ceil(period);
+        Some(cores.max(1) as usize)
+    }
+
+    pub fn get_topology() -> Topology {
+        get_topology_from(Path::new("/sys"))
+    }
+
+    /// Reads the topology of the cores from sysfs mounted at the given root,
+    /// so that it can be tested with fixtures.
+    fn get_topology_from(root: &Path) -> Topology {
+        let mut topology = Topology::default();
+
+        for (cpu, dir) in numbered_dirs(&root.join("devices/system/cpu"), "cpu") {
+            if let Some(siblings) = read_cpu_list(&dir.join("topology/thread_siblings_list")) {
+                topology.siblings.insert(cpu, siblings);
+            }
+        }
+
+        // Machines without NUMA support don't have the node directory at all.
+        for (node, dir) in numbered_dirs(&root.join("devices/system/node"), "node") {
+            for cpu in read_cpu_list(&dir.join("cpulist")).unwrap_or_default() {
+                topology.nodes.insert(cpu, node);
+            }
+        }
+
+        topology
+    }
+
+    /// Directories like `cpu0` and `node1` in the given directory, with their numbers.
+    fn numbered_dirs(dir: &Path, prefix: &str) -> Vec<(usize, PathBuf)> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let name = entry.file_name();
+                let number = name.to_str()?.strip_prefix(prefix)?.parse::<usize>().ok()?;
+
+                Some((number, entry.path()))
+            })
+            .collect()
+    }
+
+    /// Reads a cpu list of sysfs.
+    fn read_cpu_list(file: &Path) -> Option<Vec<usize>> {
+        parse_cpu_list(&fs::read_to_string(file).ok()?)
+    }
+
+    /// Parses a cpu list formatted like `0-3,8,10-11`.
+    fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+        let mut cpus = Vec::new();
+
+        for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+            let mut bounds = range.splitn(2, '-');
+            let start = bounds.next()?.parse::<usize>().ok()?;
+            let end = match bounds.next() {
+                Some(end) => end.parse::<usize>().ok()?,
+                None => start,
+            };
+
+            cpus.extend(start..=end);
+        }
+
+        Some(cpus)
+    }
+
+    #[cfg(test)]
     fn get_affinity_mask() -> Option<cpu_set_t> {
+        get_affinity_mask_of(0) // Defaults to current thread
+    }
+
+    /// Union of the affinity masks of all the threads of the process.
+    ///
+    /// The mask of the process id is only the one of its main thread.
+    fn get_process_affinity_mask() -> Option<cpu_set_t> {
+        let tasks = match fs::read_dir("/proc/self/task") {
+            Ok(tasks) => tasks,
+            Err(_) => return get_affinity_mask_of(unsafe { getpid() }),
+        };
+
+        let mut full_set = new_cpu_set();
+        let mut found = false;
+
+        let tids = tasks.filter_map(|task| task.ok()?.file_name().to_str()?.parse::<pid_t>().ok());
+        // Threads can exit while they are listed.
+        for set in tids.filter_map(get_affinity_mask_of) {
+            for i in 0..CPU_SETSIZE as usize {
+                if unsafe { CPU_ISSET(i, &set) } {
+                    unsafe { CPU_SET(i, &mut full_set) };
+                }
+            }
+            found = true;
+        }
+
+        if found {
+            Some(full_set)
+        } else {
+            get_affinity_mask_of(unsafe { getpid() })
+        }
+    }
+
+    fn get_affinity_mask_of(pid: pid_t) -> Option<cpu_set_t> {
         let mut set = new_cpu_set();
 
         // Try to get current core affinity mask.
-        let result = unsafe {
-            sched_getaffinity(
-                0, // Defaults to current thread
-                mem::size_of::<cpu_set_t>(),
-                &mut set,
-            )
-        };
+        let result = unsafe { sched_getaffinity(pid, mem::size_of::<cpu_set_t>(), &mut set) };
 
         if result == 0 {
             Some(set)
@@ -107,6 +384,7 @@ mod linux {
         use num_cpus;
 
         use super::*;
+        use crate::placement::Locality;
 
         #[test]
         fn test_linux_get_affinity_mask() {
@@ -158,6 +436,87 @@ mod linux {
 
             assert!(is_equal);
         }
+
+        fn fixture(name: &str) -> PathBuf {
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/cgroup")
+                .join(name)
+        }
+
+        #[test]
+        fn test_linux_cgroup_v2_quota() {
+            assert_eq!(get_cpu_quota_from(&fixture("v2")), Some(2));
+            assert_eq!(get_cpu_quota_from(&fixture("v2-unlimited")), None);
+        }
+
+        #[test]
+        fn test_linux_cgroup_v2_ancestor_quota() {
+            // The parent's quota is lower than the one of the process' own cgroup.
+            assert_eq!(get_cpu_quota_from(&fixture("v2-nested")), Some(1));
+        }
+
+        #[test]
+        fn test_linux_cgroup_v1_quota() {
+            assert_eq!(get_cpu_quota_from(&fixture("v1")), Some(2));
+            assert_eq!(get_cpu_quota_from(&fixture("v1-unlimited")), None);
+        }
+
+        #[test]
+        fn test_linux_cgroup_missing() {
+            assert_eq!(get_cpu_quota_from(&fixture("missing")), None);
+        }
+
+        fn topology_fixture(name: &str) -> PathBuf {
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/topology")
+                .join(name)
+                .join("sys")
+        }
+
+        fn core(id: usize) -> CoreId {
+            CoreId { id }
+        }
+
+        #[test]
+        fn test_linux_parse_cpu_list() {
+            assert_eq!(
+                parse_cpu_list("0-3,8,10-11\n"),
+                Some(vec![0, 1, 2, 3, 8, 10, 11])
+            );
+            assert_eq!(parse_cpu_list("5"), Some(vec![5]));
+            assert_eq!(parse_cpu_list("\n"), Some(vec![]));
+            assert_eq!(parse_cpu_list("0-x"), None);
+        }
+
+        #[test]
+        fn test_linux_numa_topology() {
+            let topology = get_topology_from(&topology_fixture("numa"));
+
+            assert_eq!(topology.node_of(core(5)), Some(0));
+            assert_eq!(topology.node_of(core(6)), Some(1));
+            assert!(topology.are_siblings(core(1), core(5)));
+
+            assert_eq!(topology.locality(core(0), core(0)), Locality::SameCore);
+            assert_eq!(topology.locality(core(0), core(4)), Locality::Sibling);
+            assert_eq!(topology.locality(core(0), core(1)), Locality::SameNode);
+            assert_eq!(topology.locality(core(0), core(2)), Locality::Remote);
+        }
+
+        #[test]
+        fn test_linux_smt_topology() {
+            let topology = get_topology_from(&topology_fixture("smt"));
+
+            assert_eq!(topology.node_of(core(0)), None);
+            assert_eq!(topology.locality(core(2), core(3)), Locality::Sibling);
+            assert_eq!(topology.locality(core(0), core(3)), Locality::SameNode);
+        }
+
+        #[test]
+        fn test_linux_topology_missing() {
+            let topology = get_topology_from(&topology_fixture("missing"));
+
+            assert_eq!(topology.locality(core(0), core(1)), Locality::SameNode);
+        }
     }
 }
 
@@ -394,6 +753,18 @@ fn get_core_ids_helper() -> Option<Vec<CoreId>> {
 #[inline]
 fn set_for_current_helper(core_id: CoreId) {}
 
+#[cfg(not(target_os = "linux"))]
+#[inline]
+fn get_cpu_quota_helper() -> Option<usize> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+#[inline]
+fn get_topology_helper() -> Topology {
+    Topology::default()
+}
+
 #[cfg(test)]
 mod tests {
     use num_cpus;
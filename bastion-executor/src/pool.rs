@@ -4,13 +4,30 @@
 //! Pool management and tracking belongs here.
 //! We spawn futures onto the pool with [spawn] method of global run queue or
 //! with corresponding [Worker]'s spawn method.
-use crate::distributor::Distributor;
-use crate::run_queue::{Injector, Stealer};
+use crate::config::{ExecutorConfig, DEFAULT_THREAD_NAME_PREFIX};
+use crate::deterministic::{Deterministic, VirtualClock};
+use crate::distributor::{self, Distributor};
+use crate::load_balancer::Stats;
+use crate::placement::CoreId;
+use crate::run_queue::{Injector, Steal, Stealer, Worker};
 use crate::sleepers::Sleepers;
+use crate::stats::{self, Snapshot, WorkerCounters};
+use crate::watchdog::{self, Watchdog};
 use crate::worker;
+use crossbeam_utils::sync::{Parker, ShardedLock};
+use crossbeam_utils::CachePadded;
+use fxhash::FxHashMap;
 use lazy_static::lazy_static;
 use lightproc::prelude::*;
+use lightproc::registry;
 use std::future::Future;
+use std::io;
+use std::iter;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 ///
 /// Spawn a process (which contains future + process stack) onto the executor from the global level.
@@ -45,60 +62,792 @@ where
     self::get().spawn(future, stack)
 }
 
+///
+/// Spawn a restartable process onto the executor from the global level.
+///
+/// The future is rebuilt with `factory` whenever it panics, as the `policy` allows, and the
+/// handle resolves once it completes or the process gives up.
+///
+/// # Example
+/// ```rust
+/// use bastion_executor::prelude::*;
+/// use lightproc::prelude::*;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// let attempts = AtomicUsize::new(0);
+/// let handle = spawn_restartable(
+///     move || {
+///         let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+///         async move {
+///             assert!(attempt > 0, "first attempt fails");
+///             attempt
+///         }
+///     },
+///     ProcStack::default(),
+///     RestartPolicy::default().with_max_restarts(1),
+/// );
+///
+/// assert_eq!(run(handle, ProcStack::default()), Some(1));
+/// ```
+pub fn spawn_restartable<R, F, T>(
+    factory: R,
+    stack: ProcStack,
+    policy: RestartPolicy,
+) -> RecoverableHandle<T>
+where
+    R: Fn() -> F + Send + Sync + 'static,
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    self::get().spawn_restartable(factory, stack, policy)
+}
+
+///
+/// Spawn a process onto the default pool's worker of the given core.
+///
+/// # Example
+/// ```rust
+/// use bastion_executor::placement;
+/// use bastion_executor::prelude::*;
+/// use lightproc::prelude::*;
+///
+/// let core = placement::get_core_ids().unwrap()[0];
+/// let handle = spawn_on(core, async { 1 + 1 }, ProcStack::default());
+///
+/// assert_eq!(run(handle, ProcStack::default()), Some(2));
+/// ```
+pub fn spawn_on<F, T>(core: CoreId, future: F, stack: ProcStack) -> RecoverableHandle<T>
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    self::get().spawn_on(core, future, stack)
+}
+
 ///
 /// Pool that global run queue, stealers of the workers, and parked threads.
 #[derive(Debug)]
 pub struct Pool {
+    ///
+    /// Name of the pool
+    pub(crate) name: String,
     ///
     /// Global run queue implementation
     pub(crate) injector: Injector<LightProc>,
     ///
+    /// Global run queue of the high priority procs
+    pub(crate) high_injector: Injector<LightProc>,
+    ///
+    /// Global run queue of the low priority procs
+    pub(crate) low_injector: Injector<LightProc>,
+    ///
     /// Stealers of the workers
     pub(crate) stealers: Vec<Stealer<LightProc>>,
     ///
+    /// Run queues of the procs pinned to the workers, which are never stolen
+    pub(crate) pinned: Vec<Injector<LightProc>>,
+    ///
+    /// Cores that the workers are assigned to
+    pub(crate) cores: Vec<CoreId>,
+    ///
+    /// Whether every worker only runs its own procs, without work stealing
+    pub(crate) thread_per_core: bool,
+    ///
+    /// Worker that the next proc spawned from outside of the pool goes to,
+    /// in the thread-per-core mode
+    pub(crate) next_worker: AtomicUsize,
+    ///
     /// Container of parked threads
     pub(crate) sleepers: Sleepers,
+    ///
+    /// Run queue statistics of the workers
+    pub(crate) stats: Stats,
+    ///
+    /// Runtime counters of the workers
+    pub(crate) counters: Vec<CachePadded<WorkerCounters>>,
+    ///
+    /// Cooperation points that a proc gets every time it is polled
+    pub(crate) poll_budget: usize,
+    ///
+    /// Watchdog of the workers, if enabled
+    pub(crate) watchdog: Option<Watchdog>,
+    ///
+    /// Scheduler of the procs instead of the workers, if the pool is deterministic
+    pub(crate) deterministic: Option<Deterministic>,
+    ///
+    /// Set when the pool stops accepting new procs
+    pub(crate) closing: AtomicBool,
+    ///
+    /// Set when the threads of the pool should stop
+    pub(crate) stopped: AtomicBool,
+    ///
+    /// Handles of the worker threads
+    pub(crate) threads: Mutex<Vec<JoinHandle<()>>>,
+    ///
+    /// Number of the pool's threads that are still running
+    pub(crate) running: Mutex<usize>,
+    ///
+    /// Notified every time a thread of the pool exits
+    pub(crate) exited: Condvar,
+    ///
+    /// Number of the spawned procs whose future wasn't dropped yet
+    pub(crate) live: AtomicUsize,
+}
+
+///
+/// Outcome of a [Pool::shutdown].
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    /// Stacks of the procs that were still queued when the timeout passed and got cancelled.
+    pub cancelled: Vec<ProcStack>,
+    /// Number of the procs that were waiting to be woken up, or still running on a detached
+    /// thread, when the pool stopped. They are cancelled as soon as they are woken up.
+    pub parked: usize,
+    /// Stacks of the parked procs that were spawned while the lightproc registry was enabled.
+    pub parked_stacks: Vec<ProcStack>,
+    /// Number of threads that didn't stop in time and were left detached.
+    pub detached_threads: usize,
+}
+
+impl ShutdownReport {
+    ///
+    /// Returns `true` if every spawned proc ended and every thread was joined.
+    pub fn is_clean(&self) -> bool {
+        self.cancelled.is_empty() && self.parked == 0 && self.detached_threads == 0
+    }
 }
 
 impl Pool {
+    ///
+    /// Creates a builder to start an additional, isolated pool with its own worker threads.
+    ///
+    /// Procs spawned onto a pool only run on that pool's workers, so a busy pool can't
+    /// starve the others.
+    ///
+    /// # Example
+    /// ```rust
+    /// use bastion_executor::prelude::*;
+    /// use lightproc::prelude::*;
+    ///
+    /// let batch = Pool::builder()
+    ///     .with_name("batch-doc")
+    ///     .with_config(ExecutorConfig::new().with_workers(1))
+    ///     .build()
+    ///     .expect("Pool name is already taken");
+    ///
+    /// let handle = batch.spawn(async { 1 + 1 }, ProcStack::default());
+    ///
+    /// assert_eq!(run(handle, ProcStack::default()), Some(2));
+    /// ```
+    pub fn builder() -> PoolBuilder {
+        PoolBuilder::default()
+    }
+
+    ///
     /// Error recovery for the fallen threads
-    pub fn recover_async_thread() {
-        // FIXME: Do recovery for fallen worker threads
-        unimplemented!()
+    ///
+    /// Pools recover their fallen worker threads on their own, this does nothing.
+    #[deprecated(
+        since = "0.3.1",
+        note = "pools recover their fallen worker threads on their own"
+    )]
+    pub fn recover_async_thread() {}
+
+    ///
+    /// Error recovery for the fallen worker threads.
+    ///
+    /// Procs left in the fallen worker's queue are moved to the global run queue, and a
+    /// replacement worker is started on the same core with the same queue, so the queue's
+    /// stealer stays valid.
+    pub(crate) fn recover_worker(
+        &'static self,
+        config: ExecutorConfig,
+        idx: usize,
+        core: CoreId,
+        local: Worker<LightProc>,
+        parker: Parker,
+    ) {
+        while let Some(proc) = local.pop() {
+            self.injector.push(proc);
+        }
+
+        self.stats.worker_restarts.fetch_add(1, Ordering::Relaxed);
+
+        if !self.is_stopped() {
+            distributor::spawn_worker(self, config, idx, core, local, parker);
+        }
+
+        self.sleepers.notify_one();
     }
 
     ///
     /// Spawn a process (which contains future + process stack) onto the executor via [Pool] interface.
-    pub fn spawn<F, T>(&self, future: F, stack: ProcStack) -> RecoverableHandle<T>
+    pub fn spawn<F, T>(&'static self, future: F, stack: ProcStack) -> RecoverableHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let live = Live::new(self);
+        let future = async move {
+            let _live = live;
+            future.await
+        };
+
+        let (task, handle) = LightProc::recoverable(
+            future,
+            move |proc| worker::schedule(self, proc),
+            self.place(stack),
+        );
+        self.submit(task);
+
+        handle
+    }
+
+    ///
+    /// Spawn a restartable process onto the executor via [Pool] interface.
+    ///
+    /// The future is rebuilt with `factory` whenever it panics, as the `policy` allows.
+    pub fn spawn_restartable<R, F, T>(
+        &'static self,
+        factory: R,
+        stack: ProcStack,
+        policy: RestartPolicy,
+    ) -> RecoverableHandle<T>
     where
+        R: Fn() -> F + Send + Sync + 'static,
         F: Future<Output = T> + Send + 'static,
         T: Send + 'static,
     {
-        // Log this `spawn` operation.
-        let _child_id = stack.get_pid() as u64;
-        let _parent_id = worker::get_proc_stack(|t| t.get_pid() as u64).unwrap_or(0);
+        // The factory lives as long as the future of the proc.
+        let live = Live::new(self);
+        let factory = move || {
+            let _live = &live;
+            factory()
+        };
+
+        let (task, handle) = LightProc::restartable(
+            factory,
+            move |proc| worker::schedule(self, proc),
+            self.place(stack),
+            policy,
+        );
+        self.submit(task);
 
-        let (task, handle) = LightProc::recoverable(future, worker::schedule, stack);
-        task.schedule();
         handle
     }
+
+    /// Pins the procs to a worker as soon as they are spawned, in the thread-per-core mode,
+    /// and makes the procs of a deterministic pool wait on its virtual clock.
+    ///
+    /// Procs spawned while the lightproc registry is enabled are marked with the pool,
+    /// to be listed if they are still parked when the pool shuts down.
+    fn place(&'static self, stack: ProcStack) -> ProcStack {
+        let stack = match stack.get_affinity() {
+            None if self.thread_per_core => {
+                let worker = worker::current_worker(self).unwrap_or_else(|| {
+                    self.next_worker.fetch_add(1, Ordering::Relaxed) % self.cores.len()
+                });
+                stack.with_affinity(self.cores[worker])
+            }
+            _ => stack,
+        };
+
+        let stack = if registry::is_enabled() {
+            stack.with_local(SpawnedOn(self))
+        } else {
+            stack
+        };
+
+        match &self.deterministic {
+            Some(deterministic) => stack.with_timer(VirtualClock(deterministic)),
+            None => stack,
+        }
+    }
+
+    /// Schedules a freshly spawned proc, unless the pool is closing.
+    fn submit(&self, task: LightProc) {
+        if self.closing.load(Ordering::Acquire) {
+            // Dropping the proc cancels it, handle resolves to `None`.
+            drop(task);
+        } else {
+            task.schedule();
+        }
+    }
+
+    ///
+    /// Spawn a process onto the worker of the given core, where it is going to run for
+    /// its whole life without getting stolen by the other workers.
+    ///
+    /// If none of the pool's workers are assigned to this core, the proc is cancelled and
+    /// its handle resolves to `None`.
+    pub fn spawn_on<F, T>(
+        &'static self,
+        core: CoreId,
+        future: F,
+        stack: ProcStack,
+    ) -> RecoverableHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawn(future, stack.with_affinity(core))
+    }
+
+    ///
+    /// Shuts the pool down.
+    ///
+    /// Pool stops accepting new procs and waits up to `timeout` for the queued ones to run.
+    /// Procs that are still queued after that are cancelled. Then all the sleeping threads are
+    /// woken up, and the worker threads are joined. Procs that are waiting to be woken up are
+    /// reported as parked, and get cancelled once they are woken up.
+    ///
+    /// Procs spawned onto the pool after the shutdown are cancelled right away.
+    /// A shut down pool is unregistered, so its name can be reused. The default pool is started
+    /// again by the next [get].
+    ///
+    /// # Example
+    /// ```rust
+    /// use bastion_executor::prelude::*;
+    /// use lightproc::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let pool = Pool::builder()
+    ///     .with_name("shutdown-doc")
+    ///     .with_config(ExecutorConfig::new().with_workers(1))
+    ///     .build()
+    ///     .expect("Pool name is already taken");
+    ///
+    /// let report = pool.shutdown(Duration::from_secs(1));
+    /// assert!(report.is_clean());
+    ///
+    /// let handle = pool.spawn(async { 1 + 1 }, ProcStack::default());
+    /// assert_eq!(run(handle, ProcStack::default()), None);
+    /// ```
+    pub fn shutdown(&'static self, timeout: Duration) -> ShutdownReport {
+        let deadline = Instant::now() + timeout;
+        let mut report = ShutdownReport::default();
+
+        if self.closing.swap(true, Ordering::AcqRel) {
+            // Already shut down, or shutting down from another thread.
+            return report;
+        }
+
+        // Let the workers drain the queued procs.
+        while !self.is_drained() && Instant::now() < deadline {
+            thread::sleep(SHUTDOWN_POLL_INTERVAL);
+        }
+
+        self.stopped.store(true, Ordering::Release);
+        self.sleepers.close();
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.stop();
+        }
+        report.cancelled.extend(self.cancel_queued());
+
+        // Give idle threads a chance to exit even if the deadline has already passed.
+        let deadline = deadline.max(Instant::now() + SHUTDOWN_POLL_INTERVAL);
+        let mut running = self.running.lock().unwrap();
+        while *running > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            running = self.exited.wait_timeout(running, deadline - now).unwrap().0;
+        }
+        report.detached_threads = *running;
+        drop(running);
+
+        if report.detached_threads == 0 {
+            for handle in self.threads.lock().unwrap().drain(..) {
+                let _ = handle.join();
+            }
+        }
+
+        // Procs might have been pushed while the workers were going down.
+        report.cancelled.extend(self.cancel_queued());
+        report.parked = self.live.load(Ordering::Acquire);
+        if report.parked > 0 {
+            report.parked_stacks = registry::pending_stacks()
+                .into_iter()
+                .filter(|stack| {
+                    stack
+                        .get_local::<SpawnedOn>()
+                        .is_some_and(|spawned_on| ptr::eq(spawned_on.0, self))
+                })
+                .collect();
+        }
+
+        self.unregister();
+
+        report
+    }
+
+    ///
+    /// Name of the pool.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    ///
+    /// Run queue statistics of the pool.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    ///
+    /// Takes a snapshot of the pool's runtime counters.
+    pub fn snapshot(&self) -> Snapshot {
+        stats::take(self)
+    }
+
+    ///
+    /// Scheduler of the pool, if it is deterministic.
+    pub fn deterministic(&self) -> Option<&Deterministic> {
+        self.deterministic.as_ref()
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Acquire)
+    }
+
+    ///
+    /// Number of procs waiting in the global run queues of all the priorities.
+    pub(crate) fn injector_len(&self) -> usize {
+        self.injector.len() + self.high_injector.len() + self.low_injector.len()
+    }
+
+    fn is_drained(&self) -> bool {
+        self.deterministic
+            .as_ref()
+            .is_none_or(Deterministic::is_idle)
+            && self.injector_len() == 0
+            && self.stealers.iter().all(|stealer| stealer.is_empty())
+            && self.pinned.iter().all(|pinned| pinned.is_empty())
+    }
+
+    ///
+    /// Worker that runs the procs pinned to the given core.
+    ///
+    /// Returns `None` if none of the workers are assigned to that core, e.g. the pool has
+    /// less workers than the cores.
+    pub(crate) fn pinned_worker(&self, core: CoreId) -> Option<usize> {
+        self.cores.iter().position(|assigned| *assigned == core)
+    }
+
+    /// Unregisters the pool, and lets the next [get] start a new default pool if it was the
+    /// default one.
+    fn unregister(&'static self) {
+        if self.name == DEFAULT_POOL_NAME {
+            let _pending = CONFIG.lock().unwrap();
+            let this = self as *const Pool as *mut Pool;
+            if DEFAULT
+                .compare_exchange(this, ptr::null_mut(), Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                STARTED.store(false, Ordering::Release);
+            }
+        }
+
+        let mut pools = POOLS.write().unwrap();
+        if pools
+            .get(&self.name)
+            .is_some_and(|pool| ptr::eq(*pool, self))
+        {
+            pools.remove(&self.name);
+        }
+    }
+
+    fn cancel_queued(&self) -> Vec<ProcStack> {
+        let mut cancelled = match &self.deterministic {
+            Some(deterministic) => deterministic.cancel_queued(),
+            None => Vec::new(),
+        };
+
+        loop {
+            let steal: Steal<LightProc> = iter::once(self.injector.steal())
+                .chain(iter::once(self.high_injector.steal()))
+                .chain(iter::once(self.low_injector.steal()))
+                .chain(self.stealers.iter().map(|stealer| stealer.steal()))
+                .chain(self.pinned.iter().map(|pinned| pinned.steal()))
+                .collect();
+
+            match steal {
+                // Dropping the proc cancels it.
+                Steal::Success(proc) => cancelled.push(proc.stack().clone()),
+                Steal::Retry => continue,
+                Steal::Empty => break,
+            }
+        }
+
+        cancelled
+    }
+
+    ///
+    /// Spawns a thread that belongs to the pool, so it can be joined at shutdown.
+    pub(crate) fn spawn_thread<F>(&'static self, builder: thread::Builder, f: F) -> io::Result<()>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        *self.running.lock().unwrap() += 1;
+
+        let spawned = builder.spawn(move || {
+            let _exit = ThreadExit(self);
+            f()
+        });
+
+        match spawned {
+            Ok(handle) => {
+                self.threads.lock().unwrap().push(handle);
+                Ok(())
+            }
+            Err(err) => {
+                drop(ThreadExit(self));
+                Err(err)
+            }
+        }
+    }
+
+    fn start(name: String, config: ExecutorConfig) -> &'static Pool {
+        let poll_budget = config.poll_budget;
+        let thread_per_core = config.thread_per_core;
+        let deterministic = config
+            .deterministic
+            .map(|seed| Deterministic::new(seed, poll_budget));
+        let (watchdog, watchdog_parker) = match Watchdog::new(&config) {
+            Some((watchdog, parker)) => (Some(watchdog), Some(parker)),
+            None => (None, None),
+        };
+        let distributor = Distributor::new(config);
+        let workers = distributor.cores.len();
+        let (sleepers, parkers) = Sleepers::new(workers);
+
+        let pool: &'static Pool = Box::leak(Box::new(Pool {
+            name,
+            injector: Injector::new(),
+            high_injector: Injector::new(),
+            low_injector: Injector::new(),
+            stealers: distributor.stealers(),
+            pinned: (0..workers).map(|_| Injector::new()).collect(),
+            cores: distributor.cores.clone(),
+            thread_per_core,
+            next_worker: AtomicUsize::new(0),
+            deterministic,
+            sleepers,
+            stats: Stats::new(distributor.localities()),
+            counters: (0..workers).map(|_| CachePadded::default()).collect(),
+            poll_budget,
+            watchdog,
+            closing: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            threads: Mutex::new(Vec::new()),
+            running: Mutex::new(0),
+            exited: Condvar::new(),
+            live: AtomicUsize::new(0),
+        }));
+
+        // Procs of a deterministic pool are polled by the thread that drives it.
+        if pool.deterministic.is_none() {
+            distributor.assign(pool, parkers);
+
+            if let Some(parker) = watchdog_parker {
+                watchdog::start(pool, parker);
+            }
+        }
+
+        pool
+    }
+}
+
+/// Counts a spawned proc as a live one of the pool until its future is dropped.
+struct Live(&'static Pool);
+
+impl Live {
+    fn new(pool: &'static Pool) -> Self {
+        pool.live.fetch_add(1, Ordering::AcqRel);
+        Live(pool)
+    }
+}
+
+impl Drop for Live {
+    fn drop(&mut self) {
+        self.0.live.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Local that marks the procs spawned onto a pool while the lightproc registry is enabled.
+struct SpawnedOn(&'static Pool);
+
+/// Decrements the running thread count of the pool when the thread exits.
+struct ThreadExit(&'static Pool);
+
+impl Drop for ThreadExit {
+    fn drop(&mut self) {
+        *self.0.running.lock().unwrap() -= 1;
+        self.0.exited.notify_all();
+    }
+}
+
+/// Interval of checking whether the queues are drained during the shutdown.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+///
+/// Builder of the additional pools.
+#[derive(Debug, Default)]
+pub struct PoolBuilder {
+    name: Option<String>,
+    config: ExecutorConfig,
+}
+
+impl PoolBuilder {
+    ///
+    /// Sets the name that the pool will be registered with.
+    pub fn with_name<T>(mut self, name: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.name = Some(name.into());
+        self
+    }
+
+    ///
+    /// Sets the configuration of the pool's worker threads.
+    ///
+    /// Environment variable overrides only apply to the default pool.
+    pub fn with_config(mut self, config: ExecutorConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    ///
+    /// Starts the pool and registers it with its name.
+    ///
+    /// Builder is handed back if a name wasn't given or a pool with the same name already exists.
+    pub fn build(self) -> Result<&'static Pool, Box<PoolBuilder>> {
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => return Err(Box::new(self)),
+        };
+
+        // Make sure that the default pool holds its name.
+        get();
+
+        let mut pools = POOLS.write().unwrap();
+        if pools.contains_key(&name) {
+            return Err(Box::new(self));
+        }
+
+        let config = self.config.clone();
+        let config = match config.thread_name_prefix.as_str() {
+            DEFAULT_THREAD_NAME_PREFIX => {
+                let prefix = format!("{}-{}", DEFAULT_THREAD_NAME_PREFIX, name);
+                config.with_thread_name_prefix(prefix)
+            }
+            _ => config,
+        };
+
+        let pool = Pool::start(name.clone(), config);
+        pools.insert(name, pool);
+
+        Ok(pool)
+    }
+}
+
+/// Name of the default pool.
+pub const DEFAULT_POOL_NAME: &str = "default";
+
+lazy_static! {
+    static ref CONFIG: Mutex<Option<ExecutorConfig>> = Mutex::new(None);
+    static ref POOLS: ShardedLock<FxHashMap<String, &'static Pool>> =
+        ShardedLock::new(FxHashMap::default());
+}
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// The default pool, null until it is started.
+static DEFAULT: AtomicPtr<Pool> = AtomicPtr::new(ptr::null_mut());
+
+///
+/// Configure the static Pool before it is started.
+///
+/// Environment variable overrides are applied on top of the given configuration
+/// when the pool starts. If the pool is already started, configuration is handed back.
+///
+/// # Example
+/// ```rust
+/// use bastion_executor::config::ExecutorConfig;
+/// use bastion_executor::pool;
+///
+/// let config = ExecutorConfig::new().with_workers(2);
+/// pool::configure(config).expect("Pool is already started");
+/// ```
+pub fn configure(config: ExecutorConfig) -> Result<(), ExecutorConfig> {
+    let mut pending = CONFIG.lock().unwrap();
+
+    if STARTED.load(Ordering::Acquire) {
+        return Err(config);
+    }
+
+    *pending = Some(config);
+    Ok(())
 }
 
 ///
 /// Acquire the static Pool reference
+///
+/// The default pool is started on the first call, and again on the first call after it was
+/// [shut down], with the configuration given to [configure] meanwhile.
+///
+/// [shut down]: struct.Pool.html#method.shutdown
 #[inline]
 pub fn get() -> &'static Pool {
-    lazy_static! {
-        static ref POOL: Pool = {
-            let distributor = Distributor::new();
-            let stealers = distributor.assign();
-
-            Pool {
-                injector: Injector::new(),
-                stealers,
-                sleepers: Sleepers::new(),
-            }
-        };
+    let pool = DEFAULT.load(Ordering::Acquire);
+    if pool.is_null() {
+        return start_default();
     }
-    &*POOL
+
+    unsafe { &*pool }
+}
+
+///
+/// The default pool, unless it isn't started yet or was [shut down] since it was started.
+///
+/// [shut down]: struct.Pool.html#method.shutdown
+pub fn started() -> Option<&'static Pool> {
+    unsafe { DEFAULT.load(Ordering::Acquire).as_ref() }
+}
+
+#[cold]
+fn start_default() -> &'static Pool {
+    let mut pending = CONFIG.lock().unwrap();
+
+    // Another thread could have started it while this one waited for the lock.
+    let pool = DEFAULT.load(Ordering::Acquire);
+    if !pool.is_null() {
+        return unsafe { &*pool };
+    }
+
+    STARTED.store(true, Ordering::Release);
+    let config = pending.take().unwrap_or_default();
+
+    let pool = Pool::start(DEFAULT_POOL_NAME.to_string(), config.with_env_overrides());
+    POOLS
+        .write()
+        .unwrap()
+        .insert(DEFAULT_POOL_NAME.to_string(), pool);
+    DEFAULT.store(pool as *const Pool as *mut Pool, Ordering::Release);
+
+    pool
+}
+
+///
+/// Acquire a Pool reference by its name.
+///
+/// The default pool is registered as [DEFAULT_POOL_NAME].
+pub fn named(name: &str) -> Option<&'static Pool> {
+    if name == DEFAULT_POOL_NAME {
+        return Some(get());
+    }
+
+    POOLS.read().unwrap().get(name).cloned()
 }
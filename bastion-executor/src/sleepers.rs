@@ -1,57 +1,129 @@
 //!
 //! Where workers went to parking while no workload is in their worker queue.
 //!
-//! If a workload received pool will wake them up.
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Condvar, Mutex};
+//! Every worker has its own parker. If a workload is received, pool wakes up the worker that
+//! owns it if it is parked, otherwise a single idle worker to steal it.
+use crossbeam_utils::sync::{Parker, Unparker};
+use crossbeam_utils::CachePadded;
+use std::sync::atomic::{self, AtomicBool, AtomicUsize, Ordering};
 
 /// The place where worker threads go to sleep.
 ///
-/// Similar to how thread parking works, if a notification comes up while no threads are sleeping,
-/// the next thread that attempts to go to sleep will pick up the notification immediately.
+/// Every worker marks itself as idle before parking, and checks for work once more after that.
+/// Notifications only unpark the workers that are marked as idle, and unmark them while doing so,
+/// so every parked worker is woken up once and a notification is never lost.
 #[derive(Debug)]
 pub struct Sleepers {
-    /// How many threads are currently a sleep.
-    sleep: Mutex<usize>,
+    /// Unparkers of the workers' parkers.
+    unparkers: Vec<Unparker>,
 
-    /// A condvar for notifying sleeping threads.
-    wake: Condvar,
+    /// Set to `true` while the worker is parked or about to park.
+    idle: Vec<CachePadded<AtomicBool>>,
 
-    /// Set to `true` if a notification came up while nobody was sleeping.
-    notified: AtomicBool,
+    /// How many workers are currently idle.
+    idle_count: AtomicUsize,
+
+    /// Set to `true` when sleeping is not allowed anymore, e.g. the pool is shutting down.
+    closed: AtomicBool,
 }
 
 impl Sleepers {
-    /// Creates a new `Sleepers`.
-    pub fn new() -> Sleepers {
-        Sleepers {
-            sleep: Mutex::new(0),
-            wake: Condvar::new(),
-            notified: AtomicBool::new(false),
+    /// Creates a new `Sleepers` for the given amount of workers, with their parkers.
+    pub fn new(workers: usize) -> (Sleepers, Vec<Parker>) {
+        let parkers: Vec<Parker> = (0..workers).map(|_| Parker::new()).collect();
+
+        let sleepers = Sleepers {
+            unparkers: parkers.iter().map(|p| p.unparker().clone()).collect(),
+            idle: (0..workers)
+                .map(|_| CachePadded::new(AtomicBool::new(false)))
+                .collect(),
+            idle_count: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+        };
+
+        (sleepers, parkers)
+    }
+
+    /// Puts the current worker to sleep unless `has_work` tells that there is work to do.
+    ///
+    /// `parker` has to be the parker of the worker with the index of `worker`.
+    pub fn wait<F>(&self, worker: usize, parker: &Parker, has_work: F)
+    where
+        F: FnOnce() -> bool,
+    {
+        let idle = &self.idle[worker];
+
+        idle.store(true, Ordering::SeqCst);
+        self.idle_count.fetch_add(1, Ordering::SeqCst);
+
+        // Work might have been queued before we were marked as idle.
+        atomic::fence(Ordering::SeqCst);
+        if has_work() || self.closed.load(Ordering::SeqCst) {
+            self.unmark(worker);
+            return;
+        }
+
+        // Parker might wake up spuriously, sleep until a notification unmarks us.
+        while idle.load(Ordering::SeqCst) && !self.closed.load(Ordering::SeqCst) {
+            parker.park();
         }
+
+        self.unmark(worker);
     }
 
-    /// Puts the current thread to sleep.
-    pub fn wait(&self) {
-        let mut sleep = self.sleep.lock().unwrap();
+    /// Notifies the worker that owns the work if it is idle, otherwise one of the idle workers.
+    ///
+    /// If the owner is busy, `steal` tells whether the other workers should be woken up to steal.
+    pub fn notify(&self, owner: Option<usize>, steal: bool) {
+        // Pairs with the fence in `wait`.
+        atomic::fence(Ordering::SeqCst);
 
-        if !self.notified.swap(false, Ordering::SeqCst) {
-            *sleep += 1;
-            let _ = self.wake.wait(sleep).unwrap();
+        match owner {
+            Some(owner) if self.unpark(owner) => {}
+            Some(_) if !steal => {}
+            _ => self.notify_one(),
         }
     }
 
-    /// Notifies one thread.
+    /// Notifies one idle worker.
     pub fn notify_one(&self) {
-        if !self.notified.load(Ordering::SeqCst) {
-            let mut sleep = self.sleep.lock().unwrap();
-
-            if *sleep > 0 {
-                *sleep -= 1;
-                self.wake.notify_one();
-            } else {
-                self.notified.store(true, Ordering::SeqCst);
+        atomic::fence(Ordering::SeqCst);
+
+        if self.idle_count.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+
+        for worker in 0..self.idle.len() {
+            if self.unpark(worker) {
+                return;
             }
         }
     }
+
+    /// Wakes up all the sleeping threads and prevents any thread from going to sleep again.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+
+        for worker in 0..self.idle.len() {
+            self.unpark(worker);
+        }
+    }
+
+    fn unpark(&self, worker: usize) -> bool {
+        if self.unmark(worker) {
+            self.unparkers[worker].unpark();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unmark(&self, worker: usize) -> bool {
+        if self.idle[worker].swap(false, Ordering::SeqCst) {
+            self.idle_count.fetch_sub(1, Ordering::SeqCst);
+            true
+        } else {
+            false
+        }
+    }
 }
@@ -0,0 +1,106 @@
+//!
+//! Runtime statistics of the executor
+//!
+//! Every worker counts the procs it polls, its steal attempts and the time it spends parked.
+//! Counters are updated without locking and can be read at any time with [snapshot].
+use crate::pool::{self, Pool};
+use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::time::Duration;
+
+///
+/// Counters that are updated by a single worker.
+#[derive(Debug, Default)]
+pub(crate) struct WorkerCounters {
+    pub(crate) polled: AtomicU64,
+    pub(crate) injector_steal_attempts: AtomicU64,
+    pub(crate) injector_steals: AtomicU64,
+    pub(crate) local_steal_attempts: AtomicU64,
+    pub(crate) local_steals: AtomicU64,
+    pub(crate) parked_nanos: AtomicU64,
+    pub(crate) stalls: AtomicU64,
+    pub(crate) poll_started: AtomicU64,
+    pub(crate) polling_pid: AtomicUsize,
+    pub(crate) polling_parent_pid: AtomicUsize,
+    pub(crate) polling_priority: AtomicU8,
+    pub(crate) polling_affinity: AtomicUsize,
+}
+
+///
+/// Runtime counters of a worker at the time of the snapshot.
+#[derive(Clone, Debug, Default)]
+pub struct WorkerSnapshot {
+    /// Number of procs polled by the worker.
+    pub polled: u64,
+    /// Number of attempts to steal procs from the global run queue.
+    pub injector_steal_attempts: u64,
+    /// Number of successful steals from the global run queue.
+    pub injector_steals: u64,
+    /// Number of attempts to steal procs from the local run queues of the other workers.
+    pub local_steal_attempts: u64,
+    /// Number of successful steals from the local run queues of the other workers.
+    pub local_steals: u64,
+    /// Total time the worker spent parked.
+    pub parked: Duration,
+    /// Last published length of the worker's local run queue.
+    pub queue_len: usize,
+    /// Number of procs pinned to the worker that are waiting to run.
+    pub pinned_len: usize,
+    /// Number of times the watchdog found the worker stuck polling a single proc.
+    pub stalls: u64,
+}
+
+///
+/// Runtime counters of a pool at the time of the snapshot.
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    /// Name of the pool.
+    pub pool: String,
+    /// Number of procs waiting in the global run queues.
+    pub injector_len: usize,
+    /// Number of worker threads that went down with a panic and got replaced.
+    pub worker_restarts: usize,
+    /// Counters of the workers, indexed by the worker.
+    pub workers: Vec<WorkerSnapshot>,
+}
+
+///
+/// Takes a snapshot of the default pool's runtime counters.
+///
+/// # Example
+/// ```rust
+/// use bastion_executor::stats;
+///
+/// let snapshot = stats::snapshot();
+/// for (idx, worker) in snapshot.workers.iter().enumerate() {
+///     println!("worker {} polled {} procs", idx, worker.polled);
+/// }
+/// ```
+pub fn snapshot() -> Snapshot {
+    pool::get().snapshot()
+}
+
+pub(crate) fn take(pool: &Pool) -> Snapshot {
+    let workers = pool
+        .counters
+        .iter()
+        .enumerate()
+        .map(|(idx, counters)| WorkerSnapshot {
+            polled: counters.polled.load(Ordering::Relaxed),
+            injector_steal_attempts: counters.injector_steal_attempts.load(Ordering::Relaxed),
+            injector_steals: counters.injector_steals.load(Ordering::Relaxed),
+            local_steal_attempts: counters.local_steal_attempts.load(Ordering::Relaxed),
+            local_steals: counters.local_steals.load(Ordering::Relaxed),
+            parked: Duration::from_nanos(counters.parked_nanos.load(Ordering::Relaxed)),
+            queue_len: pool.stats.queue_size(idx).unwrap_or_default(),
+            pinned_len: pool.pinned[idx].len(),
+            stalls: counters.stalls.load(Ordering::Relaxed),
+        })
+        .collect();
+
+    Snapshot {
+        pool: pool.name().to_string(),
+        injector_len: pool.injector_len(),
+        worker_restarts: pool.stats.worker_restarts(),
+        workers,
+    }
+}
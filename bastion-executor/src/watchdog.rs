@@ -0,0 +1,264 @@
+//!
+//! Watchdog for the procs that block their worker
+//!
+//! A proc which blocks inside of its poll, e.g. with synchronous I/O or a long computation,
+//! stalls its worker and the procs waiting in the worker's local run queue.
+//! When enabled with [ExecutorConfig::with_watchdog], a watchdog thread checks how long every
+//! worker has been polling its current proc, and reports the workers that are stuck for longer
+//! than the threshold. Optionally, it moves the waiting procs of a stuck worker to the global
+//! run queue so that the other workers can run them.
+//!
+//! [ExecutorConfig::with_watchdog]: ../config/struct.ExecutorConfig.html#method.with_watchdog
+use crate::config::ExecutorConfig;
+use crate::placement::CoreId;
+use crate::pool::Pool;
+use crate::run_queue::Steal;
+use crate::stats::WorkerCounters;
+use crossbeam_utils::sync::{Parker, Unparker};
+use lightproc::prelude::*;
+use log::warn;
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Bit of a poll start time which marks that the poll was already reported as stuck.
+const REPORTED: u64 = 1 << 63;
+
+///
+/// Watchdog settings of a pool.
+#[derive(Debug)]
+pub(crate) struct Watchdog {
+    /// Duration of a single poll after which the worker is reported as stuck.
+    threshold: Duration,
+    /// Whether the waiting procs of a stuck worker are moved to the global run queue.
+    rebalance: bool,
+    /// Start of the pool's clock which the poll start times are measured with.
+    epoch: Instant,
+    /// Wakes up the watchdog thread when the pool is shutting down.
+    unparker: Unparker,
+}
+
+impl Watchdog {
+    pub(crate) fn new(config: &ExecutorConfig) -> Option<(Watchdog, Parker)> {
+        let threshold = config.watchdog?;
+        let parker = Parker::new();
+
+        let watchdog = Watchdog {
+            threshold,
+            rebalance: config.watchdog_rebalance,
+            epoch: Instant::now(),
+            unparker: parker.unparker().clone(),
+        };
+
+        Some((watchdog, parker))
+    }
+
+    pub(crate) fn stop(&self) {
+        self.unparker.unpark();
+    }
+
+    /// Time passed since the epoch, never zero so that zero can stand for "not polling".
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_nanos() as u64 + 1
+    }
+}
+
+/// Details of the polled proc's stack, which are reported with its stall.
+#[derive(Clone, Copy)]
+struct Polled {
+    pid: usize,
+    parent_pid: usize,
+    priority: Priority,
+    affinity: Option<CoreId>,
+}
+
+impl Polled {
+    fn new(stack: &ProcStack) -> Self {
+        Polled {
+            pid: stack.get_pid(),
+            parent_pid: stack.get_parent_pid(),
+            priority: stack.get_priority(),
+            affinity: stack.get_affinity(),
+        }
+    }
+
+    /// Publishes the details for the watchdog thread, before the poll start time is stored.
+    fn store(&self, counters: &WorkerCounters) {
+        let priority = match self.priority {
+            Priority::Low => 0,
+            Priority::Normal => 1,
+            Priority::High => 2,
+        };
+        let affinity = self.affinity.map_or(usize::MAX, |core| core.id);
+
+        counters.polling_pid.store(self.pid, Ordering::Relaxed);
+        counters
+            .polling_parent_pid
+            .store(self.parent_pid, Ordering::Relaxed);
+        counters.polling_priority.store(priority, Ordering::Relaxed);
+        counters.polling_affinity.store(affinity, Ordering::Relaxed);
+    }
+
+    /// Reads the details of the current poll, which only belong to it as long as the poll
+    /// start time didn't change since they were read.
+    fn load(counters: &WorkerCounters) -> Self {
+        let priority = match counters.polling_priority.load(Ordering::Relaxed) {
+            0 => Priority::Low,
+            2 => Priority::High,
+            _ => Priority::Normal,
+        };
+        let affinity = match counters.polling_affinity.load(Ordering::Relaxed) {
+            usize::MAX => None,
+            id => Some(CoreId { id }),
+        };
+
+        Polled {
+            pid: counters.polling_pid.load(Ordering::Relaxed),
+            parent_pid: counters.polling_parent_pid.load(Ordering::Relaxed),
+            priority,
+            affinity,
+        }
+    }
+}
+
+impl Display for Polled {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "Proc({}) (parent: {}, priority: {:?}, affinity: {:?})",
+            self.pid, self.parent_pid, self.priority, self.affinity
+        )
+    }
+}
+
+///
+/// Polls the proc while letting the watchdog know about it.
+pub(crate) fn watch<F, R>(pool: &Pool, affinity: usize, proc: LightProc, f: F) -> R
+where
+    F: FnOnce(LightProc) -> R,
+{
+    struct Polling<'a> {
+        pool: &'a Pool,
+        watchdog: &'a Watchdog,
+        affinity: usize,
+        polled: Polled,
+        started: u64,
+    }
+
+    impl Drop for Polling<'_> {
+        fn drop(&mut self) {
+            let counters = &self.pool.counters[self.affinity];
+            let started = counters.poll_started.swap(0, Ordering::AcqRel);
+
+            // Polls that the watchdog thread already reported aren't reported again.
+            let elapsed = Duration::from_nanos(self.watchdog.now() - self.started);
+            if started & REPORTED == 0 && elapsed > self.watchdog.threshold {
+                counters.stalls.fetch_add(1, Ordering::Relaxed);
+
+                warn!(
+                    "Pool({}): Worker({}) was blocked for {:?} by {}.",
+                    self.pool.name(),
+                    self.affinity,
+                    elapsed,
+                    self.polled
+                );
+            }
+        }
+    }
+
+    let watchdog = match &pool.watchdog {
+        Some(watchdog) => watchdog,
+        None => return f(proc),
+    };
+
+    let polled = Polled::new(proc.stack());
+    let started = watchdog.now();
+
+    let counters = &pool.counters[affinity];
+    polled.store(counters);
+    counters.poll_started.store(started, Ordering::Release);
+
+    // Proc is consumed by the poll, so its stack details are kept for the report.
+    let _polling = Polling {
+        pool,
+        watchdog,
+        affinity,
+        polled,
+        started,
+    };
+
+    f(proc)
+}
+
+///
+/// Starts the watchdog thread of the pool.
+pub(crate) fn start(pool: &'static Pool, parker: Parker) {
+    let builder = thread::Builder::new().name(format!("bastion-watchdog-{}", pool.name()));
+    pool.spawn_thread(builder, move || {
+        let watchdog = pool.watchdog.as_ref().unwrap();
+        let threshold = watchdog.threshold.as_nanos() as u64;
+
+        while !pool.is_stopped() {
+            parker.park_timeout(watchdog.threshold / 2);
+
+            let now = watchdog.now();
+            for (idx, counters) in pool.counters.iter().enumerate() {
+                let started = counters.poll_started.load(Ordering::Acquire);
+                if started == 0
+                    || started & REPORTED != 0
+                    || now.saturating_sub(started) < threshold
+                {
+                    continue;
+                }
+
+                // The poll is reported either here or when it ends, whichever comes first.
+                // Details read before the start time is marked belong to this poll.
+                let polled = Polled::load(counters);
+                let marked = started | REPORTED;
+                if counters
+                    .poll_started
+                    .compare_exchange(started, marked, Ordering::AcqRel, Ordering::Acquire)
+                    .is_err()
+                {
+                    continue;
+                }
+
+                counters.stalls.fetch_add(1, Ordering::Relaxed);
+
+                warn!(
+                    "Pool({}): Worker({}) is stuck polling {} for more than {:?}.",
+                    pool.name(),
+                    idx,
+                    polled,
+                    watchdog.threshold
+                );
+
+                if watchdog.rebalance {
+                    rebalance(pool, idx);
+                }
+            }
+        }
+    })
+    .expect("watchdog couldn't start");
+}
+
+/// Moves the procs waiting in the local run queue of the worker to the global run queue.
+fn rebalance(pool: &Pool, idx: usize) {
+    let mut moved = 0;
+
+    loop {
+        match pool.stealers[idx].steal() {
+            Steal::Success(proc) => {
+                pool.injector.push(proc);
+                moved += 1;
+            }
+            Steal::Retry => continue,
+            Steal::Empty => break,
+        }
+    }
+
+    for _ in 0..moved.min(pool.stealers.len()) {
+        pool.sleepers.notify_one();
+    }
+}
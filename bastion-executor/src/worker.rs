@@ -3,11 +3,15 @@
 //!
 //! This worker implementation relies on worker run queue statistics which are hold in the pinned global memory
 //! where workload distribution calculated and amended to their own local queues.
-use crate::load_balancer;
-use crate::pool::{self, Pool};
-use crate::run_queue::{Steal, Worker};
+use crate::budget;
+use crate::pool::Pool;
+use crate::run_queue::{Injector, Steal, Worker};
+use crate::watchdog;
+use crossbeam_utils::sync::Parker;
 use lightproc::prelude::*;
 use std::cell::{Cell, UnsafeCell};
+use std::sync::atomic::Ordering;
+use std::time::Instant;
 use std::{iter, ptr};
 
 ///
@@ -57,89 +61,206 @@ where
 
 thread_local! {
     static QUEUE: UnsafeCell<Option<Worker<LightProc>>> = UnsafeCell::new(None);
+    static POOL: Cell<Option<&'static Pool>> = const { Cell::new(None) };
+    static AFFINITY: Cell<usize> = const { Cell::new(0) };
+    static TICK: Cell<usize> = const { Cell::new(0) };
 }
 
-pub(crate) fn schedule(proc: LightProc) {
+pub(crate) fn schedule(pool: &'static Pool, proc: LightProc) {
+    if pool.is_stopped() {
+        // Pool is going down, dropping the proc cancels it.
+        drop(proc);
+        return;
+    }
+
+    if let Some(deterministic) = &pool.deterministic {
+        deterministic.schedule(proc);
+        return;
+    }
+
+    // Pinned procs always go to their worker, whatever their priority is.
+    if let Some(core) = proc.stack().get_affinity() {
+        match pool.pinned_worker(core) {
+            Some(owner) => {
+                pool.pinned[owner].push(proc);
+                pool.sleepers.notify(Some(owner), false);
+            }
+            // No worker of the pool runs on this core, dropping the proc cancels it.
+            None => drop(proc),
+        }
+        return;
+    }
+
+    // Procs other than the normal priority ones go through their own global run queues.
+    match proc.stack().get_priority() {
+        Priority::High => {
+            pool.high_injector.push(proc);
+            pool.sleepers.notify_one();
+            return;
+        }
+        Priority::Low => {
+            pool.low_injector.push(proc);
+            pool.sleepers.notify_one();
+            return;
+        }
+        Priority::Normal => {}
+    }
+
     QUEUE.with(|queue| {
         let local = unsafe { (*queue.get()).as_ref() };
 
         match local {
-            None => pool::get().injector.push(proc),
-            Some(q) => q.push(proc),
+            // Only push to the local queue if this worker belongs to the given pool.
+            // Yielding procs go to the back of the global run queue.
+            Some(q) if is_current_pool(pool) && !is_yielding(&proc) => {
+                q.push(proc);
+
+                // Owner is busy running the current proc, which might block for a while,
+                // so an idle stealer is woken up to take the proc.
+                let owner = AFFINITY.with(|affinity| affinity.get());
+                pool.sleepers.notify(Some(owner), true);
+            }
+            _ => {
+                pool.injector.push(proc);
+                pool.sleepers.notify_one();
+            }
         }
     });
+}
 
-    pool::get().sleepers.notify_one();
+///
+/// Take the local run queue out of the current worker thread.
+pub(crate) fn take_local() -> Option<Worker<LightProc>> {
+    QUEUE.with(|queue| unsafe { (*queue.get()).take() })
 }
 
+///
+/// Index of the current worker, if the current thread is a worker of the given pool.
+pub(crate) fn current_worker(pool: &'static Pool) -> Option<usize> {
+    if is_current_pool(pool) {
+        Some(AFFINITY.with(|affinity| affinity.get()))
+    } else {
+        None
+    }
+}
+
+fn is_yielding(proc: &LightProc) -> bool {
+    budget::yielded() && get_proc_stack(|current| ptr::eq(current, proc.stack())).unwrap_or(false)
+}
+
+fn is_current_pool(pool: &'static Pool) -> bool {
+    POOL.with(|current| match current.get() {
+        Some(current) => ptr::eq(current, pool),
+        None => false,
+    })
+}
+
+/// Every this many fetches, normal priority procs are polled before the high priority ones.
+const NORMAL_PRIORITY_AGING: usize = 8;
+
+/// Every this many fetches, low priority procs are polled before the others.
+const LOW_PRIORITY_AGING: usize = 32;
+
 ///
 /// Fetch the process from the run queue.
 /// Does the work of work-stealing if process doesn't exist in the local run queue.
 pub fn fetch_proc(affinity: usize) -> Option<LightProc> {
-    let pool = pool::get();
+    let pool = POOL
+        .with(|pool| pool.get())
+        .expect("`fetch_proc` called outside of a worker thread");
 
-    QUEUE.with(|queue| {
+    // Only the fetches that found a proc are counted.
+    let tick = TICK.with(|tick| tick.get().wrapping_add(1));
+
+    let proc = QUEUE.with(|queue| {
         let local = unsafe { (*queue.get()).as_ref().unwrap() };
-        local.pop().or_else(|| affine_steal(pool, local, affinity))
-    })
+
+        // In the thread-per-core mode, workers only run their own procs.
+        if pool.thread_per_core {
+            return pop(&pool.pinned[affinity]).or_else(|| local.pop());
+        }
+
+        let high = || pop(&pool.high_injector);
+        let normal = || {
+            pop(&pool.pinned[affinity])
+                .or_else(|| local.pop())
+                .or_else(|| affine_steal(pool, local, affinity))
+        };
+        let low = || pop(&pool.low_injector);
+
+        // High priority procs go first, but lower priorities periodically age into
+        // the first place so that they can't starve.
+        if tick.is_multiple_of(LOW_PRIORITY_AGING) {
+            low().or_else(high).or_else(normal)
+        } else if tick.is_multiple_of(NORMAL_PRIORITY_AGING) {
+            normal().or_else(high).or_else(low)
+        } else {
+            high().or_else(normal).or_else(low)
+        }
+    });
+
+    if proc.is_some() {
+        TICK.with(|current| current.set(tick));
+    }
+
+    proc
+}
+
+fn pop(injector: &Injector<LightProc>) -> Option<LightProc> {
+    iter::repeat_with(|| injector.steal())
+        .find(|s| !s.is_retry())
+        .and_then(|s| s.success())
 }
 
 fn affine_steal(pool: &Pool, local: &Worker<LightProc>, affinity: usize) -> Option<LightProc> {
     // Pop a task from the local queue, if not empty.
     local.pop().or_else(|| {
         // Otherwise, we need to look for a task elsewhere.
-        iter::repeat_with(|| match load_balancer::stats().try_read() {
-            Ok(stats) => {
-                // Collect run queue statistics
-                let mut core_vec: Vec<_> = stats
-                    .smp_queues
-                    .iter()
-                    .map(|(&x, &y)| (x, y))
-                    .collect::<Vec<(usize, usize)>>();
-
-                // Sort cores and their run queue sizes.
-                //
-                // This sort will be ordered by descending order
-                // so we can pick up from the most overloaded queue.
-                core_vec.sort_by(|x, y| y.1.cmp(&x.1));
-
-                // First try to get procs from global queue
-                pool.injector.steal_batch_and_pop(&local).or_else(|| {
-                    match core_vec.get(0) {
-                        Some((core, _)) => {
-                            // If affinity is the one with the highest let other's do the stealing
-                            if *core == affinity {
-                                Steal::Retry
-                            } else {
-                                // Try iterating through biggest to smallest
-                                core_vec
-                                    .iter()
-                                    .map(|s| {
-                                        // Steal the mean amount to balance all queues considering incoming workloads
-                                        // Otherwise do an ignorant steal (which is going to be useless)
-                                        if stats.mean_level > 0 {
-                                            pool.stealers
-                                                .get(s.0)
-                                                .unwrap()
-                                                .steal_batch_and_pop_with_amount(
-                                                    &local,
-                                                    stats.mean_level,
-                                                )
-                                        } else {
-                                            pool.stealers
-                                                .get(s.0)
-                                                .unwrap()
-                                                .steal_batch_and_pop(&local)
-                                        }
-                                    })
-                                    .collect()
-                            }
-                        }
-                        _ => Steal::Retry,
-                    }
-                })
+        iter::repeat_with(|| {
+            // Stop looking for procs if the pool is going down.
+            if pool.is_stopped() {
+                return Steal::Empty;
+            }
+
+            let counters = &pool.counters[affinity];
+
+            // First try to get procs from global queue
+            counters
+                .injector_steal_attempts
+                .fetch_add(1, Ordering::Relaxed);
+            let steal = pool.injector.steal_batch_and_pop(&local);
+            if steal.is_success() {
+                counters.injector_steals.fetch_add(1, Ordering::Relaxed);
             }
-            Err(_) => Steal::Retry,
+
+            steal.or_else(|| {
+                let mean_level = pool.stats.mean_level();
+
+                // Try iterating through biggest to smallest
+                pool.stats
+                    .steal_order(affinity)
+                    .into_iter()
+                    .map(|core| {
+                        counters
+                            .local_steal_attempts
+                            .fetch_add(1, Ordering::Relaxed);
+
+                        // Steal the mean amount to balance all queues considering incoming workloads
+                        // Otherwise do an ignorant steal (which is going to be useless)
+                        let steal = if mean_level > 0 {
+                            pool.stealers[core].steal_batch_and_pop_with_amount(&local, mean_level)
+                        } else {
+                            pool.stealers[core].steal_batch_and_pop(&local)
+                        };
+
+                        if steal.is_success() {
+                            counters.local_steals.fetch_add(1, Ordering::Relaxed);
+                        }
+
+                        steal
+                    })
+                    .collect()
+            })
         })
         // Loop while no task was stolen and any steal operation needs to be retried.
         .find(|s| !s.is_retry())
@@ -148,29 +269,57 @@ fn affine_steal(pool: &Pool, local: &Worker<LightProc>, affinity: usize) -> Opti
     })
 }
 
-pub(crate) fn stats_generator(affinity: usize, local: &Worker<LightProc>) {
-    loop {
-        if let Ok(mut stats) = load_balancer::stats().try_write() {
-            stats
-                .smp_queues
-                .insert(affinity, local.worker_run_queue_size());
-            break;
-        }
-    }
+pub(crate) fn stats_generator(pool: &Pool, affinity: usize, local: &Worker<LightProc>) {
+    pool.stats
+        .set_queue_size(affinity, local.worker_run_queue_size());
 }
 
-pub(crate) fn main_loop(affinity: usize, local: Worker<LightProc>) {
+pub(crate) fn main_loop(
+    pool: &'static Pool,
+    affinity: usize,
+    local: Worker<LightProc>,
+    parker: &Parker,
+) {
     QUEUE.with(|queue| unsafe { *queue.get() = Some(local) });
+    POOL.with(|current| current.set(Some(pool)));
+    AFFINITY.with(|current| current.set(affinity));
 
-    loop {
+    let counters = &pool.counters[affinity];
+
+    while !pool.is_stopped() {
         QUEUE.with(|queue| {
             let local = unsafe { (*queue.get()).as_ref().unwrap() };
-            stats_generator(affinity, local);
+            stats_generator(pool, affinity, local);
         });
 
         match fetch_proc(affinity) {
-            Some(proc) => set_stack(proc.stack(), || proc.run()),
-            None => pool::get().sleepers.wait(),
+            Some(proc) => {
+                counters.polled.fetch_add(1, Ordering::Relaxed);
+                watchdog::watch(pool, affinity, proc, |proc| {
+                    let pid = proc.stack().get_pid();
+                    budget::with_budget(pool.poll_budget, pid, || {
+                        set_stack(proc.stack(), || proc.run())
+                    })
+                })
+            }
+            None => {
+                let parked = Instant::now();
+                pool.sleepers
+                    .wait(affinity, parker, || has_work(pool, affinity));
+                counters
+                    .parked_nanos
+                    .fetch_add(parked.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            }
         }
     }
 }
+
+fn has_work(pool: &Pool, affinity: usize) -> bool {
+    if pool.thread_per_core {
+        return !pool.pinned[affinity].is_empty() || !pool.stealers[affinity].is_empty();
+    }
+
+    pool.injector_len() > 0
+        || !pool.pinned[affinity].is_empty()
+        || pool.stealers.iter().any(|stealer| !stealer.is_empty())
+}
@@ -0,0 +1,21 @@
+use bastion_executor::pool;
+use bastion_executor::prelude::*;
+use lightproc::proc_stack::ProcStack;
+use std::ptr;
+use std::time::Duration;
+
+#[test]
+fn default_pool_restarts() {
+    let first = pool::get();
+    assert!(first.shutdown(Duration::from_secs(1)).is_clean());
+
+    let second = pool::get();
+    assert!(!ptr::eq(first, second));
+    assert!(ptr::eq(
+        pool::named(pool::DEFAULT_POOL_NAME).unwrap(),
+        second
+    ));
+
+    let handle = pool::spawn(async { 42 }, ProcStack::default());
+    assert_eq!(run(handle, ProcStack::default()), Some(42));
+}
@@ -1,6 +1,15 @@
 #[cfg(test)]
 mod tests {
-    use bastion_executor::{placement, pool};
+    use bastion_executor::placement::{self, CoreId};
+    use bastion_executor::prelude::*;
+    use bastion_executor::{budget, pool};
+    use lightproc::proc_stack::{Priority, ProcStack};
+    use lightproc::recoverable_handle::RecoverableHandle;
+    use lightproc::registry;
+    use lightproc::restart_policy::RestartPolicy;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     #[test]
     fn affinity_replacement() {
@@ -12,4 +21,600 @@ mod tests {
     fn pool_check() {
         pool::get();
     }
+
+    #[test]
+    fn named_pool_check() {
+        let config = ExecutorConfig::new().with_workers(1);
+        let batch = Pool::builder()
+            .with_name("batch")
+            .with_config(config.clone())
+            .build()
+            .unwrap();
+
+        assert_eq!(batch.name(), "batch");
+        assert!(std::ptr::eq(pool::named("batch").unwrap(), batch));
+        assert!(std::ptr::eq(
+            pool::named(pool::DEFAULT_POOL_NAME).unwrap(),
+            pool::get()
+        ));
+
+        // Names are unique.
+        assert!(Pool::builder()
+            .with_name("batch")
+            .with_config(config)
+            .build()
+            .is_err());
+
+        let handle = batch.spawn(async { 42 }, ProcStack::default());
+        assert_eq!(run(handle, ProcStack::default()), Some(42));
+    }
+
+    #[test]
+    fn pool_shutdown() {
+        let config = ExecutorConfig::new().with_workers(2).with_pinning(false);
+
+        for _ in 0..3 {
+            let short_lived = Pool::builder()
+                .with_name("short-lived")
+                .with_config(config.clone())
+                .build()
+                .unwrap();
+
+            let handle = short_lived.spawn(async { 42 }, ProcStack::default());
+            assert_eq!(run(handle, ProcStack::default()), Some(42));
+
+            let report = short_lived.shutdown(Duration::from_secs(1));
+            assert!(report.is_clean());
+            assert!(pool::named("short-lived").is_none());
+
+            // Procs aren't accepted anymore.
+            let handle = short_lived.spawn(async { 42 }, ProcStack::default());
+            assert_eq!(run(handle, ProcStack::default()), None);
+        }
+    }
+
+    #[test]
+    fn poll_budget_without_consume() {
+        let config = ExecutorConfig::new()
+            .with_workers(1)
+            .with_pinning(false)
+            .with_poll_budget(4);
+        let spinning = Pool::builder()
+            .with_name("spinning")
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        // Keeps waking itself up without ever consuming its budget.
+        let done = Arc::new(AtomicBool::new(false));
+        let spinner_done = done.clone();
+        let spinner = spinning.spawn(
+            std::future::poll_fn(move |cx| {
+                if spinner_done.load(Ordering::SeqCst) {
+                    return std::task::Poll::Ready(());
+                }
+
+                cx.waker().wake_by_ref();
+                std::task::Poll::Pending
+            }),
+            ProcStack::default(),
+        );
+
+        let other = spinning.spawn(
+            async move { done.store(true, Ordering::SeqCst) },
+            ProcStack::default(),
+        );
+
+        assert_eq!(run(other, ProcStack::default()), Some(()));
+        assert_eq!(run(spinner, ProcStack::default()), Some(()));
+        spinning.shutdown(Duration::from_secs(1));
+    }
+
+    #[test]
+    fn pool_shutdown_reports_parked() {
+        // Parked procs are only listed if they were spawned while the registry is enabled.
+        registry::enable();
+
+        let parking = Pool::builder()
+            .with_name("parking")
+            .with_config(ExecutorConfig::new().with_workers(1))
+            .build()
+            .unwrap();
+
+        // Nothing ever wakes the proc up, the handle keeps it from being deallocated.
+        let _handle = parking.spawn(
+            std::future::pending::<()>(),
+            ProcStack::default().with_pid(7),
+        );
+
+        let report = parking.shutdown(Duration::from_secs(1));
+        let parked: Vec<_> = report
+            .parked_stacks
+            .iter()
+            .map(ProcStack::get_pid)
+            .collect();
+        assert_eq!(report.parked, 1);
+        assert_eq!(parked, vec![7]);
+        assert!(report.cancelled.is_empty());
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn fallen_worker_recovery() {
+        let config = ExecutorConfig::new().with_workers(1);
+        let fragile = Pool::builder()
+            .with_name("fragile")
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        // Panics outside of the proc's future take the worker thread down.
+        let stack = ProcStack::default().with_before_start(|| panic!("worker goes down"));
+        let handle = fragile.spawn(async { 1 }, stack);
+        assert_eq!(run(handle, ProcStack::default()), None);
+
+        let handle = fragile.spawn(async { 2 }, ProcStack::default());
+        assert_eq!(run(handle, ProcStack::default()), Some(2));
+        assert_eq!(fragile.stats().worker_restarts(), 1);
+    }
+
+    #[test]
+    fn pool_snapshot() {
+        let config = ExecutorConfig::new().with_workers(2);
+        let counted = Pool::builder()
+            .with_name("counted")
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        for i in 0..10 {
+            let handle = counted.spawn(async move { i }, ProcStack::default());
+            assert_eq!(run(handle, ProcStack::default()), Some(i));
+        }
+
+        let snapshot = counted.snapshot();
+        assert_eq!(snapshot.pool, "counted");
+        assert_eq!(snapshot.workers.len(), 2);
+        assert_eq!(snapshot.injector_len, 0);
+
+        let polled: u64 = snapshot.workers.iter().map(|w| w.polled).sum();
+        let injector_steals: u64 = snapshot.workers.iter().map(|w| w.injector_steals).sum();
+        assert!(polled >= 10);
+        assert!(injector_steals > 0);
+    }
+
+    #[test]
+    fn priority_order() {
+        let config = ExecutorConfig::new().with_workers(1);
+        let prioritized = Pool::builder()
+            .with_name("prioritized")
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        // Keep the only worker busy until all the procs are queued.
+        let started = Arc::new(AtomicBool::new(false));
+        let release = Arc::new(AtomicBool::new(false));
+        let (started_, release_) = (started.clone(), release.clone());
+        let blocker = prioritized.spawn(
+            async move {
+                started_.store(true, Ordering::SeqCst);
+                while !release_.load(Ordering::SeqCst) {
+                    std::thread::yield_now();
+                }
+            },
+            ProcStack::default(),
+        );
+
+        while !started.load(Ordering::SeqCst) {
+            std::thread::yield_now();
+        }
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let handles: Vec<_> = vec![Priority::Low, Priority::Normal, Priority::High]
+            .into_iter()
+            .map(|priority| {
+                let order = order.clone();
+                prioritized.spawn(
+                    async move { order.lock().unwrap().push(priority) },
+                    ProcStack::default().with_priority(priority),
+                )
+            })
+            .collect();
+
+        release.store(true, Ordering::SeqCst);
+        run(blocker, ProcStack::default());
+        for handle in handles {
+            run(handle, ProcStack::default());
+        }
+
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![Priority::High, Priority::Normal, Priority::Low]
+        );
+    }
+
+    /// Spawns a proc which keeps its worker busy until it is released.
+    fn block_worker(pool: &'static Pool) -> (RecoverableHandle<()>, Arc<AtomicBool>) {
+        let started = Arc::new(AtomicBool::new(false));
+        let release = Arc::new(AtomicBool::new(false));
+        let (started_, release_) = (started.clone(), release.clone());
+        let blocker = pool.spawn(
+            async move {
+                started_.store(true, Ordering::SeqCst);
+                while !release_.load(Ordering::SeqCst) {
+                    std::thread::yield_now();
+                }
+            },
+            ProcStack::default(),
+        );
+
+        while !started.load(Ordering::SeqCst) {
+            std::thread::yield_now();
+        }
+
+        (blocker, release)
+    }
+
+    #[test]
+    fn poll_budget() {
+        let config = ExecutorConfig::new().with_workers(1).with_poll_budget(16);
+        let budgeted = Pool::builder()
+            .with_name("budgeted")
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        // Keep the only worker busy until both procs are queued.
+        let (blocker, release) = block_worker(budgeted);
+
+        // A proc that is always ready gets rescheduled once it spends its budget,
+        // so the procs queued after it get to run in between.
+        let other_ran = Arc::new(AtomicBool::new(false));
+        let other_ran_ = other_ran.clone();
+        let busy = budgeted.spawn(
+            async move {
+                for _ in 0..1_000 {
+                    if other_ran_.load(Ordering::SeqCst) {
+                        return true;
+                    }
+
+                    budget::consume().await;
+                }
+
+                false
+            },
+            ProcStack::default(),
+        );
+
+        let other = budgeted.spawn(
+            async move { other_ran.store(true, Ordering::SeqCst) },
+            ProcStack::default(),
+        );
+
+        release.store(true, Ordering::SeqCst);
+        run(blocker, ProcStack::default());
+        assert_eq!(run(busy, ProcStack::default()), Some(true));
+        assert_eq!(run(other, ProcStack::default()), Some(()));
+    }
+
+    #[test]
+    fn yield_to_others() {
+        let config = ExecutorConfig::new().with_workers(1);
+        let yielding = Pool::builder()
+            .with_name("yielding")
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        // Keep the only worker busy until both procs are queued.
+        let (blocker, release) = block_worker(yielding);
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let (first, second) = (order.clone(), order.clone());
+
+        let polite = yielding.spawn(
+            async move {
+                bastion_executor::yield_now().await;
+                first.lock().unwrap().push("polite");
+            },
+            ProcStack::default(),
+        );
+        let other = yielding.spawn(
+            async move { second.lock().unwrap().push("other") },
+            ProcStack::default(),
+        );
+
+        release.store(true, Ordering::SeqCst);
+        run(blocker, ProcStack::default());
+        run(polite, ProcStack::default());
+        run(other, ProcStack::default());
+
+        assert_eq!(*order.lock().unwrap(), vec!["other", "polite"]);
+    }
+
+    #[test]
+    fn watchdog_stalls() {
+        let config = ExecutorConfig::new()
+            .with_workers(2)
+            .with_watchdog(Duration::from_millis(20))
+            .with_watchdog_rebalance(true);
+        let watched = Pool::builder()
+            .with_name("watched")
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        // The blocking proc holds its worker well past the threshold, while the proc
+        // it spawns is run by the other worker.
+        let blocking = watched.spawn(
+            async move {
+                let quick_ran = Arc::new(AtomicBool::new(false));
+                let quick_ran_ = quick_ran.clone();
+                let quick = watched.spawn(
+                    async move { quick_ran_.store(true, Ordering::SeqCst) },
+                    ProcStack::default(),
+                );
+
+                let started = std::time::Instant::now();
+                while !quick_ran.load(Ordering::SeqCst)
+                    || started.elapsed() < Duration::from_millis(100)
+                {
+                    if started.elapsed() > Duration::from_secs(5) {
+                        return false;
+                    }
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+
+                quick.await.is_some()
+            },
+            ProcStack::default(),
+        );
+
+        assert_eq!(run(blocking, ProcStack::default()), Some(true));
+
+        let stalls: u64 = watched.snapshot().workers.iter().map(|w| w.stalls).sum();
+        assert!(stalls >= 1);
+    }
+
+    #[test]
+    fn watchdog_counts_every_stall() {
+        let config = ExecutorConfig::new()
+            .with_workers(1)
+            .with_watchdog(Duration::from_millis(20));
+        let stalling = Pool::builder()
+            .with_name("stalling")
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        // Stalls that end before the watchdog thread gets to them are counted as well.
+        for _ in 0..5 {
+            let blocking = stalling.spawn(
+                async { std::thread::sleep(Duration::from_millis(25)) },
+                ProcStack::default(),
+            );
+            run(blocking, ProcStack::default());
+        }
+
+        let stalls: u64 = stalling.snapshot().workers.iter().map(|w| w.stalls).sum();
+        assert_eq!(stalls, 5);
+        stalling.shutdown(Duration::from_secs(1));
+    }
+
+    #[test]
+    fn pinned_procs() {
+        let config = ExecutorConfig::new()
+            .with_workers(2)
+            .with_thread_name_prefix("pinned");
+        let pinned = Pool::builder()
+            .with_name("pinned")
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        let core = placement::get_core_ids().unwrap()[0];
+        let stack = move || ProcStack::default().with_affinity(core);
+        let thread_name = || std::thread::current().name().map(String::from);
+
+        let handle = pinned.spawn(
+            async move {
+                // Their worker is kept busy after spawning them,
+                // so the other worker would steal them if it could.
+                let handles: Vec<_> = (0..10)
+                    .map(|_| pinned.spawn(async move { thread_name() }, stack()))
+                    .collect();
+                std::thread::sleep(Duration::from_millis(50));
+
+                let mut names = vec![thread_name()];
+                for handle in handles {
+                    names.push(handle.await.unwrap());
+                }
+                names
+            },
+            stack(),
+        );
+
+        let names = run(handle, ProcStack::default()).unwrap();
+        assert_eq!(names.len(), 11);
+        assert!(names.iter().all(|name| name.as_deref() == Some("pinned-0")));
+    }
+
+    #[test]
+    fn thread_per_core() {
+        let config = ExecutorConfig::new()
+            .with_thread_per_core(true)
+            .with_thread_name_prefix("sharded");
+        let sharded = Pool::builder()
+            .with_name("sharded")
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        let thread_name = || std::thread::current().name().map(String::from);
+
+        // Procs spawned from outside of the pool go to the workers in round-robin fashion,
+        // and the procs they spawn stay on the same worker.
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                sharded.spawn(
+                    async move {
+                        let children: Vec<_> = (0..10)
+                            .map(|_| {
+                                sharded.spawn(async move { thread_name() }, ProcStack::default())
+                            })
+                            .collect();
+                        std::thread::sleep(Duration::from_millis(10));
+
+                        let parent = thread_name();
+                        for child in children {
+                            if child.await.unwrap() != parent {
+                                return None;
+                            }
+                        }
+                        parent
+                    },
+                    ProcStack::default(),
+                )
+            })
+            .collect();
+
+        for handle in handles {
+            let name = run(handle, ProcStack::default()).unwrap();
+            assert!(name.unwrap().starts_with("sharded-"));
+        }
+
+        let core = placement::get_core_ids().unwrap()[0];
+        let handle = sharded.spawn_on(core, async move { thread_name() }, ProcStack::default());
+        assert_eq!(
+            run(handle, ProcStack::default()).unwrap().as_deref(),
+            Some("sharded-0")
+        );
+
+        // Procs pinned to a core without a worker are rejected.
+        let unknown = CoreId { id: usize::MAX };
+        let handle = sharded.spawn_on(unknown, async {}, ProcStack::default());
+        assert_eq!(run(handle, ProcStack::default()), None);
+
+        let snapshot = sharded.snapshot();
+        assert!(snapshot.workers.iter().all(|w| w.local_steals == 0));
+    }
+
+    fn interleaving(name: &str, seed: u64) -> Vec<usize> {
+        let config = ExecutorConfig::new().with_deterministic(seed);
+        let pool = Pool::builder()
+            .with_name(name)
+            .with_config(config)
+            .build()
+            .unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for id in 0..8 {
+            let order = order.clone();
+            pool.spawn(
+                async move {
+                    for _ in 0..4 {
+                        order.lock().unwrap().push(id);
+                        bastion_executor::yield_now().await;
+                    }
+                },
+                ProcStack::default(),
+            );
+        }
+
+        let scheduler = pool.deterministic().unwrap();
+        assert_eq!(scheduler.run_until_stalled(), 8 * 5);
+        pool.shutdown(Duration::from_secs(1));
+
+        let order = order.lock().unwrap().clone();
+        order
+    }
+
+    #[test]
+    fn deterministic_replay() {
+        let first = interleaving("replayed-1", 7);
+        let second = interleaving("replayed-2", 7);
+        let other = interleaving("replayed-3", 8);
+
+        assert_eq!(first.len(), 32);
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn deterministic_clock() {
+        let config = ExecutorConfig::new().with_deterministic(0);
+        let pool = Pool::builder()
+            .with_name("virtual-clock")
+            .with_config(config)
+            .build()
+            .unwrap();
+        let scheduler = pool.deterministic().unwrap();
+
+        let woken = Arc::new(Mutex::new(Vec::new()));
+        let handles: Vec<_> = vec![30, 10, 20]
+            .into_iter()
+            .map(|secs| {
+                let woken = woken.clone();
+                pool.spawn(
+                    async move {
+                        scheduler.sleep(Duration::from_secs(secs)).await;
+                        woken
+                            .lock()
+                            .unwrap()
+                            .push((secs, scheduler.now().as_secs()));
+                    },
+                    ProcStack::default(),
+                )
+            })
+            .collect();
+
+        scheduler.advance(Duration::from_secs(15));
+        assert_eq!(*woken.lock().unwrap(), vec![(10, 10)]);
+        assert_eq!(scheduler.now(), Duration::from_secs(15));
+
+        scheduler.advance(Duration::from_secs(15));
+        assert_eq!(*woken.lock().unwrap(), vec![(10, 10), (20, 20), (30, 30)]);
+
+        for handle in handles {
+            assert_eq!(scheduler.block_on(handle), Some(Some(())));
+        }
+
+        // Nothing is left to drive the future.
+        let sleeping = scheduler.sleep(Duration::from_secs(1));
+        assert_eq!(scheduler.block_on(sleeping), None);
+    }
+
+    #[test]
+    fn deterministic_restart_backoff() {
+        let config = ExecutorConfig::new().with_deterministic(0);
+        let pool = Pool::builder()
+            .with_name("virtual-backoff")
+            .with_config(config)
+            .build()
+            .unwrap();
+        let scheduler = pool.deterministic().unwrap();
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let attempts_ = attempts.clone();
+        let handle = pool.spawn_restartable(
+            move || {
+                let attempt = attempts_.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    assert!(attempt > 0, "first attempt fails");
+                    attempt
+                }
+            },
+            ProcStack::default(),
+            RestartPolicy::default().with_backoff(Duration::from_secs(10)),
+        );
+
+        // The backoff passes on the virtual clock.
+        scheduler.run_until_stalled();
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        scheduler.advance(Duration::from_secs(10));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(scheduler.block_on(handle), Some(Some(1)));
+    }
 }
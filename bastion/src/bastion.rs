@@ -4,8 +4,14 @@ use crate::config::Config;
 use crate::message::{BastionMessage, Message};
 use crate::supervisor::{Supervisor, SupervisorRef};
 use crate::system::{System, SYSTEM, SYSTEM_SENDER};
+use bastion_executor::pool;
+use lightproc::registry;
 use std::fmt::{self, Debug, Formatter};
 use std::thread;
+use std::time::Duration;
+
+/// Time that the executor is given to run its queued procs while it shuts down.
+const EXECUTOR_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
 
 /// A `struct` allowing to access the system's API to initialize it,
 /// start, stop and kill it and to create new supervisors and top-level
@@ -211,6 +217,10 @@ impl Bastion {
             std::panic::set_hook(Box::new(|_| ()));
         }
 
+        if pool::configure(config.executor().clone()).is_err() {
+            warn!("Bastion: Executor is already started, ignoring its config.");
+        }
+
         // NOTE: this is just to make sure that SYSTEM_SENDER has been initialized by lazy_static
         SYSTEM_SENDER.is_closed();
     }
@@ -423,6 +433,9 @@ impl Bastion {
     /// Sends a message to the system to tell it to stop
     /// every running children groups and supervisors.
     ///
+    /// Once the system stopped, [`Bastion::block_until_stopped()`]
+    /// shuts the executor down and joins its threads.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -442,6 +455,8 @@ impl Bastion {
     ///     # Bastion::block_until_stopped();
     /// }
     /// ```
+    ///
+    /// [`Bastion::block_until_stopped()`]: #method.block_until_stopped
     pub fn stop() {
         debug!("Bastion: Stopping.");
         let msg = BastionMessage::stop();
@@ -453,6 +468,10 @@ impl Bastion {
     /// Sends a message to the system to tell it to kill every
     /// running children groups and supervisors
     ///
+    /// When called from outside of the system, the executor is shut
+    /// down right away and its threads are joined. Otherwise, it is
+    /// shut down by [`Bastion::block_until_stopped()`].
+    ///
     /// # Example
     ///
     /// ```rust
@@ -471,6 +490,8 @@ impl Bastion {
     ///     # Bastion::block_until_stopped();
     /// }
     /// ```
+    ///
+    /// [`Bastion::block_until_stopped()`]: #method.block_until_stopped
     pub fn kill() {
         debug!("Bastion: Killing.");
         let msg = BastionMessage::kill();
@@ -482,7 +503,13 @@ impl Bastion {
         let mut system = SYSTEM.clone().lock().wait().unwrap();
         if let Some(system) = system.take() {
             debug!("Bastion: Cancelling system handle.");
-            system.cancel();
+            system.cancel_with("Bastion::kill");
+        }
+        drop(system);
+
+        // The executor can't join its threads from one of them.
+        if registry::current_pid().is_none() {
+            Bastion::shutdown_executor();
         }
     }
 
@@ -490,6 +517,9 @@ impl Bastion {
     /// (either by calling [`Bastion::stop()`] or
     /// [`Bastion::kill`]).
     ///
+    /// The executor is then shut down, its remaining procs are
+    /// cancelled and its threads are joined.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -519,11 +549,118 @@ impl Bastion {
             // FIXME: panics
             let system = SYSTEM.clone().lock().wait().unwrap();
             if system.is_none() {
+                drop(system);
+                Bastion::shutdown_executor();
+
                 debug!("Bastion: Unblocking because system is stopped.");
                 return;
             }
+            drop(system);
+
+            // A deterministic system only makes progress when driven.
+            match pool::get().deterministic() {
+                Some(deterministic) => {
+                    deterministic.run_until_stalled();
+                }
+                None => thread::yield_now(),
+            }
+        }
+    }
+
+    /// Shuts the executor down once the system is stopped, unless
+    /// it is already shut down. The executor is started again if
+    /// the system is initialized again.
+    fn shutdown_executor() {
+        // Only the pool that is running is shut down, `pool::get`
+        // would start a new one.
+        let pool = match pool::started() {
+            Some(pool) => pool,
+            None => return,
+        };
+
+        debug!("Bastion: Shutting the executor down.");
+        let report = pool.shutdown(EXECUTOR_SHUTDOWN_TIMEOUT);
+        if !report.is_clean() {
+            warn!(
+                "Bastion: Executor shut down with {} cancelled procs, {} parked procs and {} detached threads.",
+                report.cancelled.len(),
+                report.parked,
+                report.detached_threads
+            );
+        }
+    }
+
+    /// Runs the supervisors and children of a deterministic system
+    /// (see [`Config::deterministic`]) on the current thread, until
+    /// none of them can make progress.
+    ///
+    /// This method returns how many times the supervisors and
+    /// children were polled. It does nothing and returns `0` if the
+    /// system isn't deterministic.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// fn main() {
+    ///     Bastion::init_with(Config::deterministic(42));
+    ///
+    ///     // Use bastion, spawn children and supervisors...
+    ///
+    ///     Bastion::start();
+    ///     // Let the system handle the messages...
+    ///     Bastion::run_until_stalled();
+    ///     #
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// }
+    /// ```
+    ///
+    /// [`Config::deterministic`]: struct.Config.html#method.deterministic
+    pub fn run_until_stalled() -> usize {
+        match pool::get().deterministic() {
+            Some(deterministic) => deterministic.run_until_stalled(),
+            None => {
+                warn!("Bastion: Running a system that isn't deterministic.");
+                0
+            }
+        }
+    }
 
-            thread::yield_now();
+    /// Moves the virtual clock of a deterministic system (see
+    /// [`Config::deterministic`]) forward by the given duration,
+    /// running its supervisors and children meanwhile.
+    ///
+    /// It does nothing if the system isn't deterministic.
+    ///
+    /// # Arguments
+    ///
+    /// * `by` - How much time should pass.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// fn main() {
+    ///     Bastion::init_with(Config::deterministic(42));
+    ///     Bastion::start();
+    ///
+    ///     // A minute passes for the system in no time...
+    ///     Bastion::advance(Duration::from_secs(60));
+    ///     #
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// }
+    /// ```
+    ///
+    /// [`Config::deterministic`]: struct.Config.html#method.deterministic
+    pub fn advance(by: Duration) {
+        match pool::get().deterministic() {
+            Some(deterministic) => deterministic.advance(by),
+            None => warn!("Bastion: Advancing a system that isn't deterministic."),
         }
     }
 }
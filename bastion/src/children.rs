@@ -5,7 +5,9 @@ use crate::broadcast::{Broadcast, Parent, Sender};
 use crate::callbacks::Callbacks;
 use crate::context::{BastionContext, BastionId, ContextState};
 use crate::message::{Answer, BastionMessage, Message};
-use bastion_executor::pool;
+use bastion_executor::budget;
+use bastion_executor::placement::CoreId;
+use bastion_executor::pool::{self, Pool};
 use futures::pending;
 use futures::poll;
 use futures::prelude::*;
@@ -85,6 +87,13 @@ pub struct Children {
     // is received.
     pre_start_msgs: Vec<BastionMessage>,
     started: bool,
+    // The executor pool that the group and its elements run
+    // on, if it is not the default one.
+    pool: Option<&'static Pool>,
+    // The scheduling priority of the group's elements.
+    priority: Priority,
+    // The core that the group's elements are pinned to, if any.
+    core_affinity: Option<CoreId>,
 }
 
 #[derive(Debug, Clone)]
@@ -111,6 +120,10 @@ pub(crate) struct Child {
     // is received.
     pre_start_msgs: Vec<BastionMessage>,
     started: bool,
+    // The scheduling priority of the child's future.
+    priority: Priority,
+    // The core that the child's future is pinned to, if any.
+    core_affinity: Option<CoreId>,
 }
 
 #[derive(Debug, Clone)]
@@ -147,6 +160,9 @@ impl Children {
         let callbacks = Callbacks::new();
         let pre_start_msgs = Vec::new();
         let started = false;
+        let pool = None;
+        let priority = Priority::default();
+        let core_affinity = None;
 
         Children {
             bcast,
@@ -156,13 +172,19 @@ impl Children {
             callbacks,
             pre_start_msgs,
             started,
+            pool,
+            priority,
+            core_affinity,
         }
     }
 
     fn stack(&self) -> ProcStack {
         trace!("Children({}): Creating ProcStack.", self.id());
-        // FIXME: with_pid
+        // The group handles the lifecycle messages of its elements,
+        // so it has to outrank them.
         ProcStack::default()
+            .with_priority(Priority::High)
+            .with_local(self.id().clone())
     }
 
     pub(crate) async fn reset(&mut self, bcast: Broadcast) {
@@ -392,6 +414,177 @@ impl Children {
         self
     }
 
+    /// Sets the name of the executor pool that this children group
+    /// and its elements will run on.
+    ///
+    /// By default, a children group runs on the same pool as its
+    /// supervisor. The pool needs to be started with
+    /// [`Pool::builder`] first.
+    ///
+    /// This method returns the children group back if a pool
+    /// with this name was started, or `Err(())` otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the executor pool.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// use bastion_executor::pool::{self, Pool};
+    /// #
+    /// # fn main() {
+    ///     # Bastion::init();
+    ///     #
+    /// Pool::builder()
+    ///     .with_name("requests")
+    ///     .with_config(ExecutorConfig::new().with_workers(1))
+    ///     .build()
+    ///     .expect("Couldn't start the pool.");
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_pool("requests")
+    ///         .expect("Unknown pool.")
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // This future will run on the "requests" pool...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Pool::builder`]: https://docs.rs/bastion-executor/latest/bastion_executor/pool/struct.Pool.html#method.builder
+    pub fn with_pool(mut self, name: &str) -> Result<Self, ()> {
+        trace!("Children({}): Setting pool: {}", self.id(), name);
+        match pool::named(name) {
+            Some(pool) => {
+                self.pool = Some(pool);
+                Ok(self)
+            }
+            None => {
+                // TODO: Err(Error)
+                error!("Children({}): Unknown pool: {}", self.id(), name);
+                Err(())
+            }
+        }
+    }
+
+    /// Sets the scheduling priority of this children group's elements.
+    ///
+    /// Elements with a higher priority are polled before the
+    /// others when the executor is busy, but the ones with a lower
+    /// priority still get their turn. The children group itself,
+    /// its supervisor and the system always run with
+    /// [`Priority::High`] so that their lifecycle messages can't
+    /// be delayed by user work.
+    ///
+    /// By default, elements run with [`Priority::Normal`].
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - The priority of the elements' futures.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # fn main() {
+    ///     # Bastion::init();
+    ///     #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_priority(Priority::Low)
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // This future will only run when there is
+    ///                 // nothing more important to do...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Priority::High`]: https://docs.rs/lightproc/latest/lightproc/proc_stack/enum.Priority.html
+    /// [`Priority::Normal`]: https://docs.rs/lightproc/latest/lightproc/proc_stack/enum.Priority.html
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        trace!("Children({}): Setting priority: {:?}", self.id(), priority);
+        self.priority = priority;
+        self
+    }
+
+    /// Pins this children group's elements to the worker of the
+    /// given core.
+    ///
+    /// Pinned elements are always polled by the same worker and
+    /// are never stolen by the other workers, which keeps their
+    /// latency predictable and their data in the core's caches.
+    /// The children group itself isn't pinned.
+    ///
+    /// If the executor pool has no worker on this core, the
+    /// elements are cancelled as soon as they are launched.
+    ///
+    /// # Arguments
+    ///
+    /// * `core` - The core that the elements' futures will run on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// use bastion_executor::placement;
+    /// #
+    /// # fn main() {
+    ///     # Bastion::init();
+    ///     #
+    /// let core = placement::get_core_ids()
+    ///     .expect("Couldn't get the cores.")[0];
+    ///
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_core_affinity(core)
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // This future will always run on the first
+    ///                 // core's worker...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_core_affinity(mut self, core: CoreId) -> Self {
+        trace!("Children({}): Setting core affinity: {:?}", self.id(), core);
+        self.core_affinity = Some(core);
+        self
+    }
+
+    pub(crate) fn inherit_pool(mut self, pool: Option<&'static Pool>) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    pub(crate) fn pool(&self) -> &'static Pool {
+        self.pool.unwrap_or_else(pool::get)
+    }
+
     async fn stop(&mut self) {
         debug!("Children({}): Stopping.", self.id());
         self.bcast.stop_children();
@@ -412,15 +605,26 @@ impl Children {
 
         let mut children = FuturesOrdered::new();
         for (_, (_, launched)) in self.launched.drain() {
-            launched.cancel();
+            launched.cancel_with("killed");
 
-            children.push(launched);
+            children.push(launched.into_outcome());
         }
 
+        let id = self.id();
         children
-            .for_each_concurrent(None, |_| {
-                async {
-                    trace!("Children({}): Unknown child stopped.", self.id());
+            .for_each_concurrent(None, |outcome| {
+                async move {
+                    match outcome {
+                        ProcOutcome::Completed(()) => {
+                            trace!("Children({}): Unknown child stopped.", id)
+                        }
+                        ProcOutcome::Panicked(info) => {
+                            warn!("Children({}): Unknown child {}.", id, info)
+                        }
+                        ProcOutcome::Cancelled(_) => {
+                            trace!("Children({}): Unknown child killed.", id)
+                        }
+                    }
                 }
             })
             .await;
@@ -467,8 +671,13 @@ impl Children {
             }
             BastionMessage::Stopped { id } => {
                 // FIXME: Err if false?
-                if self.launched.contains_key(&id) {
-                    debug!("Children({}): Child({}) stopped.", self.id(), id);
+                if let Some((_, launched)) = self.launched.get(&id) {
+                    debug!(
+                        "Children({}): Child({}) stopped after {}.",
+                        self.id(),
+                        id,
+                        launched.metrics()
+                    );
                     self.stop().await;
                     self.stopped();
 
@@ -477,8 +686,13 @@ impl Children {
             }
             BastionMessage::Faulted { id } => {
                 // FIXME: Err if false?
-                if self.launched.contains_key(&id) {
-                    warn!("Children({}): Child({}) faulted.", self.id(), id);
+                if let Some((_, launched)) = self.launched.get(&id) {
+                    warn!(
+                        "Children({}): Child({}) faulted after {}.",
+                        self.id(),
+                        id,
+                        launched.metrics()
+                    );
                     self.kill().await;
                     self.faulted();
 
@@ -493,6 +707,9 @@ impl Children {
     async fn run(mut self) -> Self {
         debug!("Children({}): Launched.", self.id());
         loop {
+            // Let the other procs run if this loop keeps being ready.
+            budget::consume().await;
+
             for (_, launched) in self.launched.values_mut() {
                 let _ = poll!(launched);
             }
@@ -569,7 +786,14 @@ impl Children {
             let state = ContextState::new();
             let state = Qutex::new(state);
 
-            let ctx = BastionContext::new(id, child_ref, children, supervisor, state.clone());
+            let ctx = BastionContext::new(
+                id,
+                child_ref,
+                children,
+                supervisor,
+                state.clone(),
+                self.pool,
+            );
             let exec = (self.init.0)(ctx);
 
             self.bcast.register(&bcast);
@@ -579,10 +803,10 @@ impl Children {
                 self.id(),
                 bcast.id()
             );
-            let child = Child::new(exec, bcast, state);
+            let child = Child::new(exec, bcast, state, self.priority, self.core_affinity);
             debug!("Children({}): Launching Child({}).", self.id(), child.id());
             let id = child.id().clone();
-            let launched = child.launch();
+            let launched = child.launch(self.pool());
 
             self.launched.insert(id, (sender, launched));
         }
@@ -591,7 +815,8 @@ impl Children {
     pub(crate) fn launch(self) -> RecoverableHandle<Self> {
         debug!("Children({}): Launching.", self.id());
         let stack = self.stack();
-        pool::spawn(self.run(), stack)
+        let pool = self.pool();
+        pool.spawn(self.run(), stack)
     }
 }
 
@@ -789,7 +1014,13 @@ impl ChildrenRef {
 }
 
 impl Child {
-    fn new(exec: Exec, bcast: Broadcast, state: Qutex<ContextState>) -> Self {
+    fn new(
+        exec: Exec,
+        bcast: Broadcast,
+        state: Qutex<ContextState>,
+        priority: Priority,
+        core_affinity: Option<CoreId>,
+    ) -> Self {
         debug!("Child({}): Initializing.", bcast.id());
         let pre_start_msgs = Vec::new();
         let started = false;
@@ -800,25 +1031,42 @@ impl Child {
             state,
             pre_start_msgs,
             started,
+            priority,
+            core_affinity,
         }
     }
 
     fn stack(&self) -> ProcStack {
         trace!("Child({}): Creating ProcStack.", self.id());
         let id = self.bcast.id().clone();
+        let cancelled_id = id.clone();
         // FIXME: panics?
         let parent = self.bcast.parent().clone().into_children().unwrap();
 
-        // FIXME: with_pid
-        ProcStack::default().with_after_panic(move || {
-            // FIXME: clones
-            let id = id.clone();
-            warn!("Child({}): Panicked.", id);
-
-            let msg = BastionMessage::faulted(id);
-            // TODO: handle errors
-            parent.send(msg).ok();
-        })
+        let stack = ProcStack::default()
+            .with_priority(self.priority)
+            .with_local(self.id().clone())
+            .with_after_cancel(move |reason| {
+                debug!(
+                    "Child({}): Cancelled: {}.",
+                    cancelled_id,
+                    reason.unwrap_or("no reason")
+                );
+            })
+            .with_after_panic_info(move |info| {
+                // FIXME: clones
+                let id = id.clone();
+                warn!("Child({}): Panicked: {}.", id, info);
+
+                let msg = BastionMessage::faulted(id);
+                // TODO: handle errors
+                parent.send(msg).ok();
+            });
+
+        match self.core_affinity {
+            Some(core) => stack.with_affinity(core),
+            None => stack,
+        }
     }
 
     fn id(&self) -> &BastionId {
@@ -871,6 +1119,9 @@ impl Child {
     async fn run(mut self) {
         debug!("Child({}): Launched.", self.id());
         loop {
+            // Let the other procs run if this loop keeps being ready.
+            budget::consume().await;
+
             match poll!(&mut self.bcast.next()) {
                 // TODO: Err if started == true?
                 Poll::Ready(Some(BastionMessage::Start)) => {
@@ -952,9 +1203,9 @@ impl Child {
         }
     }
 
-    fn launch(self) -> RecoverableHandle<()> {
+    fn launch(self, pool: &'static Pool) -> RecoverableHandle<()> {
         let stack = self.stack();
-        pool::spawn(self.run(), stack)
+        pool.spawn(self.run(), stack)
     }
 }
 
@@ -1,9 +1,13 @@
+use bastion_executor::config::ExecutorConfig;
+
 #[derive(Default, Debug, Clone)]
 /// The configuration that should be used to initialize the
 /// system using [`Bastion::init_with`].
 ///
 /// The default behaviors are the following:
 /// - All backtraces are shown (see [`Config::show_backtraces`]).
+/// - The executor starts a pinned worker per core (see
+///     [`Config::with_executor`]).
 ///
 /// # Example
 ///
@@ -24,8 +28,11 @@
 /// ```
 ///
 /// [`Bastion::init_with`]: struct.Bastion.html#method.init_with
+/// [`Config::show_backtraces`]: #method.show_backtraces
+/// [`Config::with_executor`]: #method.with_executor
 pub struct Config {
     backtraces: Backtraces,
+    executor: ExecutorConfig,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -107,9 +114,102 @@ impl Config {
         self
     }
 
+    /// Sets the configuration of the executor that runs the
+    /// supervisors and children.
+    ///
+    /// Note that the executor is shared by the whole process, so
+    /// this configuration is only used if the executor wasn't
+    /// already started. Environment variables (like
+    /// `BASTION_WORKERS`) override this configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - The configuration of the executor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// fn main() {
+    ///     let executor = ExecutorConfig::new()
+    ///         .with_workers(2)
+    ///         .with_pinning(false)
+    ///         .with_thread_name_prefix("my-service");
+    ///     let config = Config::new().with_executor(executor);
+    ///
+    ///     Bastion::init_with(config);
+    ///
+    ///     // You can now use bastion and it will only use two
+    ///     // unpinned threads...
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// }
+    /// ```
+    pub fn with_executor(mut self, executor: ExecutorConfig) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Creates a new configuration which runs the system
+    /// deterministically, for reproducible tests.
+    ///
+    /// Instead of the executor's worker threads, the supervisors
+    /// and children are run one at a time by the thread calling
+    /// [`Bastion::run_until_stalled`] (or
+    /// [`Bastion::block_until_stopped`]), in an order picked by the
+    /// given seed. Time only passes when [`Bastion::advance`] is
+    /// called. Running a test with the same seed always
+    /// interleaves the children the same way, so a failure can be
+    /// replayed from its seed.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed of the scheduling order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// fn main() {
+    ///     Bastion::init_with(Config::deterministic(42));
+    ///
+    ///     Bastion::children(|children| {
+    ///         children.with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // This future will only be polled
+    ///                 // by `Bastion::run_until_stalled`...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    ///     }).expect("Couldn't create the children group.");
+    ///
+    ///     Bastion::start();
+    ///     Bastion::run_until_stalled();
+    ///     #
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// }
+    /// ```
+    ///
+    /// [`Bastion::run_until_stalled`]: struct.Bastion.html#method.run_until_stalled
+    /// [`Bastion::block_until_stopped`]: struct.Bastion.html#method.block_until_stopped
+    /// [`Bastion::advance`]: struct.Bastion.html#method.advance
+    pub fn deterministic(seed: u64) -> Self {
+        let executor = ExecutorConfig::new().with_deterministic(seed);
+        Config::new().with_executor(executor)
+    }
+
     pub(crate) fn backtraces(&self) -> &Backtraces {
         &self.backtraces
     }
+
+    pub(crate) fn executor(&self) -> &ExecutorConfig {
+        &self.executor
+    }
 }
 
 impl Backtraces {
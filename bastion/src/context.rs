@@ -5,10 +5,13 @@
 use crate::children::{ChildRef, ChildrenRef};
 use crate::message::Msg;
 use crate::supervisor::SupervisorRef;
+use bastion_executor::pool::{self, Pool};
 use futures::pending;
+use lightproc::prelude::*;
 use qutex::{Guard, Qutex};
 use std::collections::VecDeque;
 use std::fmt::{self, Display, Formatter};
+use std::future::Future;
 use uuid::Uuid;
 
 pub(crate) const NIL_ID: BastionId = BastionId(Uuid::nil());
@@ -100,6 +103,9 @@ pub struct BastionContext {
     children: ChildrenRef,
     supervisor: Option<SupervisorRef>,
     state: Qutex<ContextState>,
+    // The executor pool that the element runs on, if it is not
+    // the default one.
+    pool: Option<&'static Pool>,
 }
 
 #[derive(Debug)]
@@ -113,6 +119,36 @@ impl BastionId {
 
         BastionId(uuid)
     }
+
+    /// Returns the identifier of the element whose process is
+    /// currently running, if any.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # fn main() {
+    ///     # Bastion::init();
+    ///     #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx| {
+    ///         async move {
+    ///             assert_eq!(BastionId::current().as_ref(), Some(ctx.current().id()));
+    ///             // ...
+    ///             # Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn current() -> Option<Self> {
+        lightproc::local::get()
+    }
 }
 
 impl BastionContext {
@@ -122,6 +158,7 @@ impl BastionContext {
         children: ChildrenRef,
         supervisor: Option<SupervisorRef>,
         state: Qutex<ContextState>,
+        pool: Option<&'static Pool>,
     ) -> Self {
         debug!("BastionContext({}): Creating.", id);
         BastionContext {
@@ -130,6 +167,7 @@ impl BastionContext {
             children,
             supervisor,
             state,
+            pool,
         }
     }
 
@@ -258,6 +296,63 @@ impl BastionContext {
         self.supervisor.as_ref()
     }
 
+    /// Spawns a future on the executor pool of the element this
+    /// `BastionContext` is linked to, tied to the element.
+    ///
+    /// When spawned from the element's future, the spawned future
+    /// is cancelled as soon as the element stops, is killed, faults
+    /// or is restarted, so it never outlives it. It can read the
+    /// same process-local values as the element, like its
+    /// [`BastionId::current`].
+    ///
+    /// This method returns a [`RecoverableHandle`] that resolves
+    /// to the output of the future, or `None` if it panicked or
+    /// was cancelled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # fn main() {
+    ///     # Bastion::init();
+    ///     #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let helper = ctx.spawn(async {
+    ///                 // Work that must not outlive the element...
+    ///                 1 + 1
+    ///             });
+    ///
+    ///             assert_eq!(helper.await, Some(2));
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionId::current`]: struct.BastionId.html#method.current
+    /// [`RecoverableHandle`]: ../../lightproc/recoverable_handle/struct.RecoverableHandle.html
+    pub fn spawn<F, T>(&self, future: F) -> RecoverableHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        debug!("BastionContext({}): Spawning a future.", self.id);
+        let stack = ProcStack::default()
+            .with_scoped(true)
+            .with_inherited_locals(true);
+
+        self.pool.unwrap_or_else(pool::get).spawn(future, stack)
+    }
+
     /// Tries to retrieve asynchronously a message received by
     /// the element this `BastionContext` is linked to.
     ///
@@ -85,4 +85,7 @@ pub mod prelude {
     pub use crate::message::{Answer, Message, Msg, Sender};
     pub use crate::msg;
     pub use crate::supervisor::{SupervisionStrategy, Supervisor, SupervisorRef};
+    pub use bastion_executor::config::ExecutorConfig;
+    pub use lightproc::proc_stack::Priority;
+    pub use lightproc::recoverable_handle::RecoverableHandle;
 }
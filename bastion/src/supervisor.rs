@@ -6,7 +6,8 @@ use crate::callbacks::Callbacks;
 use crate::children::{Children, ChildrenRef};
 use crate::context::BastionId;
 use crate::message::{BastionMessage, Deployment, Message};
-use bastion_executor::pool;
+use bastion_executor::budget;
+use bastion_executor::pool::{self, Pool};
 use futures::prelude::*;
 use futures::stream::FuturesOrdered;
 use futures::{pending, poll};
@@ -82,6 +83,10 @@ pub struct Supervisor {
     // is received.
     pre_start_msgs: Vec<BastionMessage>,
     started: bool,
+    // The executor pool that the supervisor and its supervised
+    // children and supervisors run on, if it is not the default
+    // one.
+    pool: Option<&'static Pool>,
 }
 
 #[derive(Debug, Clone)]
@@ -92,6 +97,7 @@ pub struct Supervisor {
 pub struct SupervisorRef {
     id: BastionId,
     sender: Sender,
+    pool: Option<&'static Pool>,
 }
 
 #[derive(Debug, Clone)]
@@ -139,6 +145,7 @@ impl Supervisor {
         let is_system_supervisor = false;
         let pre_start_msgs = Vec::new();
         let started = false;
+        let pool = None;
 
         Supervisor {
             bcast,
@@ -151,6 +158,7 @@ impl Supervisor {
             is_system_supervisor,
             pre_start_msgs,
             started,
+            pool,
         }
     }
 
@@ -163,8 +171,10 @@ impl Supervisor {
 
     fn stack(&self) -> ProcStack {
         trace!("Supervisor({}): Creating ProcStack.", self.id());
-        // FIXME: with_pid
+        // Supervision has to outrank the supervised elements' work.
         ProcStack::default()
+            .with_priority(Priority::High)
+            .with_local(self.id().clone())
     }
 
     pub(crate) async fn reset(&mut self, bcast: Option<Broadcast>) {
@@ -268,8 +278,9 @@ impl Supervisor {
         // TODO: clone or ref?
         let id = self.bcast.id().clone();
         let sender = self.bcast.sender().clone();
+        let pool = self.pool;
 
-        SupervisorRef::new(id, sender)
+        SupervisorRef::new(id, sender, pool)
     }
 
     /// Creates a new supervisor, passes it through the specified
@@ -321,7 +332,7 @@ impl Supervisor {
             self.id(),
             bcast.id()
         );
-        let supervisor = Supervisor::new(bcast);
+        let supervisor = Supervisor::new(bcast).inherit_pool(self.pool);
         let supervisor = init(supervisor);
         debug!("Supervisor({}): Initialized.", supervisor.id());
 
@@ -386,7 +397,7 @@ impl Supervisor {
             self.id(),
             bcast.id()
         );
-        let supervisor = Supervisor::new(bcast);
+        let supervisor = Supervisor::new(bcast).inherit_pool(self.pool);
         let supervisor = init(supervisor);
         debug!("Supervisor({}): Initialized.", supervisor.id());
         let supervisor_ref = supervisor.as_ref();
@@ -460,7 +471,7 @@ impl Supervisor {
             self.id(),
             bcast.id()
         );
-        let children = Children::new(bcast);
+        let children = Children::new(bcast).inherit_pool(self.pool);
         let mut children = init(children);
         debug!("Children({}): Initialized.", children.id());
         // FIXME: children group elems launched without the group itself being launched
@@ -536,7 +547,7 @@ impl Supervisor {
             self.id(),
             bcast.id()
         );
-        let children = Children::new(bcast);
+        let children = Children::new(bcast).inherit_pool(self.pool);
         let mut children = init(children);
         debug!("Children({}): Initialized.", children.id());
         // FIXME: children group elems launched without the group itself being launched
@@ -655,6 +666,75 @@ impl Supervisor {
         self
     }
 
+    /// Sets the name of the executor pool that this supervisor and
+    /// its supervised children groups and supervisors will run on.
+    ///
+    /// Children groups and supervisors created by this supervisor
+    /// run on the same pool, unless they are given another one.
+    /// The pool needs to be started with [`Pool::builder`] first.
+    ///
+    /// This method returns the supervisor back if a pool with
+    /// this name was started, or `Err(())` otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the executor pool.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// use bastion_executor::pool::{self, Pool};
+    /// #
+    /// # fn main() {
+    ///     # Bastion::init();
+    ///     #
+    /// Pool::builder()
+    ///     .with_name("batch")
+    ///     .with_config(ExecutorConfig::new().with_workers(1))
+    ///     .build()
+    ///     .expect("Couldn't start the pool.");
+    ///
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_pool("batch")
+    ///         .expect("Unknown pool.")
+    ///         .children(|children| {
+    ///             // This children group will run on the "batch" pool...
+    ///             # children
+    ///         })
+    /// }).expect("Couldn't create the supervisor.");
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Pool::builder`]: https://docs.rs/bastion-executor/latest/bastion_executor/pool/struct.Pool.html#method.builder
+    pub fn with_pool(mut self, name: &str) -> Result<Self, ()> {
+        trace!("Supervisor({}): Setting pool: {}", self.id(), name);
+        match pool::named(name) {
+            Some(pool) => {
+                self.pool = Some(pool);
+                Ok(self)
+            }
+            None => {
+                // TODO: Err(Error)
+                error!("Supervisor({}): Unknown pool: {}", self.id(), name);
+                Err(())
+            }
+        }
+    }
+
+    fn inherit_pool(mut self, pool: Option<&'static Pool>) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    fn pool(&self) -> &'static Pool {
+        self.pool.unwrap_or_else(pool::get)
+    }
+
     async fn restart(&mut self, range: RangeFrom<usize>) {
         debug!("Supervisor({}): Restarting range: {:?}", self.id(), range);
         // TODO: stop or kill?
@@ -982,6 +1062,9 @@ impl Supervisor {
     async fn run(mut self) -> Self {
         debug!("Supervisor({}): Launched.", self.id());
         loop {
+            // Let the other procs run if this loop keeps being ready.
+            budget::consume().await;
+
             match poll!(&mut self.bcast.next()) {
                 // TODO: Err if started == true?
                 Poll::Ready(Some(BastionMessage::Start)) => {
@@ -1040,13 +1123,14 @@ impl Supervisor {
     pub(crate) fn launch(self) -> RecoverableHandle<Self> {
         debug!("Supervisor({}): Launching.", self.id());
         let stack = self.stack();
-        pool::spawn(self.run(), stack)
+        let pool = self.pool();
+        pool.spawn(self.run(), stack)
     }
 }
 
 impl SupervisorRef {
-    pub(crate) fn new(id: BastionId, sender: Sender) -> Self {
-        SupervisorRef { id, sender }
+    pub(crate) fn new(id: BastionId, sender: Sender, pool: Option<&'static Pool>) -> Self {
+        SupervisorRef { id, sender, pool }
     }
 
     /// Returns the identifier of the supervisor this `SupervisorRef`
@@ -1127,7 +1211,7 @@ impl SupervisorRef {
             self.id(),
             bcast.id()
         );
-        let supervisor = Supervisor::new(bcast);
+        let supervisor = Supervisor::new(bcast).inherit_pool(self.pool);
         let supervisor = init(supervisor);
         let supervisor_ref = supervisor.as_ref();
         debug!("Supervisor({}): Initialized.", supervisor.id());
@@ -1200,7 +1284,7 @@ impl SupervisorRef {
             self.id(),
             bcast.id()
         );
-        let children = Children::new(bcast);
+        let children = Children::new(bcast).inherit_pool(self.pool);
         let mut children = init(children);
         debug!("Children({}): Initialized.", children.id());
         // FIXME: children group elems launched without the group itself being launched
@@ -1415,8 +1499,9 @@ impl Supervised {
 
     fn stack(&self) -> ProcStack {
         trace!("Supervised({}): Creating ProcStack.", self.id());
-        // FIXME: with_id
         ProcStack::default()
+            .with_priority(Priority::High)
+            .with_local(self.id().clone())
     }
 
     fn reset(self, bcast: Broadcast) -> RecoverableHandle<Self> {
@@ -1426,15 +1511,16 @@ impl Supervised {
             bcast.id()
         );
         let stack = self.stack();
+        let pool = self.pool();
         match self {
-            Supervised::Supervisor(mut supervisor) => pool::spawn(
+            Supervised::Supervisor(mut supervisor) => pool.spawn(
                 async {
                     supervisor.reset(Some(bcast)).await;
                     Supervised::Supervisor(supervisor)
                 },
                 stack,
             ),
-            Supervised::Children(mut children) => pool::spawn(
+            Supervised::Children(mut children) => pool.spawn(
                 async {
                     children.reset(bcast).await;
                     Supervised::Children(children)
@@ -1465,12 +1551,20 @@ impl Supervised {
         }
     }
 
+    fn pool(&self) -> &'static Pool {
+        match self {
+            Supervised::Supervisor(supervisor) => supervisor.pool(),
+            Supervised::Children(children) => children.pool(),
+        }
+    }
+
     fn launch(self) -> RecoverableHandle<Self> {
         debug!("Supervised({}): Launching.", self.id());
         let stack = self.stack();
+        let pool = self.pool();
         match self {
             Supervised::Supervisor(supervisor) => {
-                pool::spawn(
+                pool.spawn(
                     async {
                         // FIXME: panics?
                         let supervisor = supervisor.launch().await.unwrap();
@@ -1480,7 +1574,7 @@ impl Supervised {
                 )
             }
             Supervised::Children(children) => {
-                pool::spawn(
+                pool.spawn(
                     async {
                         // FIXME: panics?
                         let children = children.launch().await.unwrap();
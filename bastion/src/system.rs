@@ -79,7 +79,7 @@ impl System {
 
     fn stack(&self) -> ProcStack {
         // FIXME: with_id
-        ProcStack::default()
+        ProcStack::default().with_priority(Priority::High)
     }
 
     pub(crate) fn root_supervisor() -> Option<&'static SupervisorRef> {
@@ -136,11 +136,11 @@ impl System {
         self.bcast.kill_children();
 
         for launched in self.waiting.iter_mut() {
-            launched.cancel();
+            launched.cancel_with("killed");
         }
 
         for (_, launched) in self.launched.drain() {
-            launched.cancel();
+            launched.cancel_with("killed");
 
             self.waiting.push(launched);
         }
@@ -0,0 +1,47 @@
+use bastion::prelude::*;
+use futures::future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn spawned_futures_end_with_the_child() {
+    Bastion::init_with(Config::deterministic(7));
+    Bastion::start();
+
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let dropped_ = dropped.clone();
+    Bastion::children(move |children| {
+        children.with_exec(move |ctx: BastionContext| {
+            let dropped = dropped_.clone();
+            async move {
+                let id = ctx.current().id().clone();
+                // The helper can get cancelled before its first poll, which drops it unpolled.
+                let counter = DropCounter(dropped);
+                let helper = ctx.spawn(async move {
+                    assert_eq!(BastionId::current(), Some(id));
+
+                    let _counter = counter;
+                    future::pending::<()>().await
+                });
+                drop(helper);
+
+                Ok(())
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    Bastion::run_until_stalled();
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
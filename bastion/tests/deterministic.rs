@@ -0,0 +1,31 @@
+use bastion::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn deterministic_children() {
+    Bastion::init_with(Config::deterministic(42));
+    Bastion::start();
+
+    let started = Arc::new(AtomicUsize::new(0));
+    let started_ = started.clone();
+    Bastion::children(move |children| {
+        children.with_redundancy(3).with_exec(move |_ctx| {
+            let started = started_.clone();
+            async move {
+                started.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    // Nothing runs until the system is driven.
+    assert_eq!(started.load(Ordering::SeqCst), 0);
+
+    assert!(Bastion::run_until_stalled() > 0);
+    assert_eq!(started.load(Ordering::SeqCst), 3);
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
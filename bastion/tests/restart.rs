@@ -0,0 +1,25 @@
+use bastion::prelude::*;
+use bastion_executor::pool;
+use bastion_executor::run::run;
+use lightproc::proc_stack::ProcStack;
+
+#[test]
+fn restarted_executor_shut_down() {
+    Bastion::init();
+    Bastion::start();
+    assert!(pool::started().is_some());
+
+    Bastion::kill();
+    Bastion::block_until_stopped();
+    assert!(pool::started().is_none());
+
+    // The executor is started again by the first proc spawned after that.
+    Bastion::init();
+    let handle = pool::spawn(async { 1 + 1 }, ProcStack::default());
+    assert_eq!(run(handle, ProcStack::default()), Some(2));
+    assert!(pool::started().is_some());
+
+    Bastion::kill();
+    Bastion::block_until_stopped();
+    assert!(pool::started().is_none());
+}
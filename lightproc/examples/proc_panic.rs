@@ -38,8 +38,8 @@ where
             .with_after_complete(|| {
                 println!("After complete");
             })
-            .with_after_panic(|| {
-                println!("After panic");
+            .with_after_panic_info(|info| {
+                println!("After panic: {}", info);
             }),
     );
 
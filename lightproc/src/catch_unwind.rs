@@ -1,5 +1,5 @@
+use crate::panic_info::PanicInfo;
 use pin_utils::unsafe_pinned;
-use std::any::Any;
 use std::future::Future;
 use std::panic::{catch_unwind, AssertUnwindSafe, UnwindSafe};
 use std::pin::Pin;
@@ -28,9 +28,11 @@ impl<F> Future for CatchUnwind<F>
 where
     F: Future + UnwindSafe,
 {
-    type Output = Result<F::Output, Box<dyn Any + Send>>;
+    type Output = Result<F::Output, PanicInfo>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        catch_unwind(AssertUnwindSafe(|| self.future().poll(cx)))?.map(Ok)
+        catch_unwind(AssertUnwindSafe(|| self.future().poll(cx)))
+            .map_err(PanicInfo::new)?
+            .map(Ok)
     }
 }
@@ -0,0 +1,374 @@
+//!
+//! Combinators of the handles of processes
+//!
+//! Handles can be awaited all together with [JoinAll], raced with [Select] and bounded in time
+//! with [Timeout].
+//!
+//! [Select] follows the processes themselves: as soon as one of them ends, the other ones are
+//! cancelled, even if the [Select] isn't polled again. A [Timeout] cancels its process when it
+//! is polled after the time ran out. Cancellation happens on the state of the processes, so
+//! they are not polled again even if they are already scheduled.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lightproc::prelude::*;
+//! use futures::future;
+//!
+//! let (fast, fast_handle) = LightProc::build(async { 1 }, |_| {}, ProcStack::default());
+//! let (_slow, slow_handle) =
+//!     LightProc::build(future::pending::<usize>(), |_| {}, ProcStack::default());
+//! fast.run();
+//!
+//! let handles = vec![slow_handle, fast_handle];
+//! let (index, output) = futures::executor::block_on(ProcHandle::select(handles));
+//! assert_eq!((index, output), (1, Some(1)));
+//! ```
+//!
+//! [JoinAll]: struct.JoinAll.html
+//! [Select]: struct.Select.html
+//! [Timeout]: struct.Timeout.html
+use crate::delay::Delay;
+use crate::proc_data::ProcData;
+use crate::proc_ref::ProcRef;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// Winner of a [Select] which has none yet.
+const NO_WINNER: usize = usize::MAX;
+
+///
+/// Handles of processes, which the combinators work with.
+///
+/// This trait is sealed, it is only implemented by the handles of this crate.
+pub trait Cancellable: sealed::Handle {
+    ///
+    /// Cancels the process with the given reason, unless it already ended.
+    fn cancel_with(&self, reason: &str);
+}
+
+pub(crate) mod sealed {
+    use crate::proc_stack::ProcStack;
+    use std::ptr::NonNull;
+
+    pub trait Handle {
+        /// Returns the pointer to the process of the handle.
+        fn raw_proc(&self) -> NonNull<()>;
+
+        /// Returns the stack of the process of the handle.
+        fn stack(&self) -> &ProcStack;
+    }
+}
+
+///
+/// Future resolving to the outputs of all the given processes, in their order.
+///
+/// Created with `join_all` on the handles. It is woken once, when all the processes ended.
+pub struct JoinAll<H>
+where
+    H: Future,
+{
+    handles: Vec<Option<H>>,
+    outputs: Vec<Option<H::Output>>,
+    countdown: Arc<Countdown>,
+}
+
+///
+/// Future resolving to the index and the output of the first of the given processes which
+/// ends.
+///
+/// Created with `select` on the handles. The other processes are cancelled as soon as the first
+/// one ends. It never resolves if there are no handles.
+pub struct Select<H> {
+    handles: Vec<H>,
+    race: Arc<Race>,
+}
+
+///
+/// Future resolving to the output of the process, or to `None` once the process ran out of time
+/// and got cancelled.
+///
+/// Created with `with_timeout` on a handle. The time passes on the timer of the process' stack.
+pub struct Timeout<H> {
+    handle: H,
+    delay: Delay,
+}
+
+/// Number of the processes of a [JoinAll] which didn't end yet.
+struct Countdown {
+    remaining: AtomicUsize,
+    waker: Mutex<Option<Waker>>,
+}
+
+/// Processes of a [Select], racing to end first.
+struct Race {
+    winner: AtomicUsize,
+    /// References to the processes, released once the race is over.
+    members: Mutex<Vec<ProcRef>>,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl<H> JoinAll<H>
+where
+    H: Future + Cancellable,
+{
+    pub(crate) fn new<I>(handles: I) -> Self
+    where
+        I: IntoIterator<Item = H>,
+    {
+        let handles: Vec<_> = handles.into_iter().map(Some).collect();
+        let outputs = handles.iter().map(|_| None).collect();
+        let countdown = Arc::new(Countdown {
+            remaining: AtomicUsize::new(handles.len()),
+            waker: Mutex::default(),
+        });
+
+        for handle in handles.iter().flatten() {
+            let countdown = countdown.clone();
+            pdata(handle).on_end(Box::new(move || countdown.count()));
+        }
+
+        JoinAll {
+            handles,
+            outputs,
+            countdown,
+        }
+    }
+}
+
+// The outputs are never pinned.
+impl<H> Unpin for JoinAll<H> where H: Future + Unpin {}
+
+impl<H> Select<H>
+where
+    H: Cancellable,
+{
+    pub(crate) fn new<I>(handles: I) -> Self
+    where
+        I: IntoIterator<Item = H>,
+    {
+        let handles: Vec<_> = handles.into_iter().collect();
+        let race = Arc::new(Race {
+            winner: AtomicUsize::new(NO_WINNER),
+            members: Mutex::new(
+                handles
+                    .iter()
+                    .map(|handle| ProcRef::new(handle.raw_proc()))
+                    .collect(),
+            ),
+            waker: Mutex::default(),
+        });
+
+        // The members are known before any process can end and cancel the other ones.
+        for (index, handle) in handles.iter().enumerate() {
+            let race = race.clone();
+            pdata(handle).on_end(Box::new(move || race.finish(index)));
+        }
+
+        Select { handles, race }
+    }
+}
+
+impl<H> Timeout<H>
+where
+    H: Cancellable,
+{
+    pub(crate) fn new(handle: H, timeout: Duration) -> Self {
+        let delay = Delay::new(timeout, handle.stack().timer.as_ref());
+
+        Timeout { handle, delay }
+    }
+}
+
+impl Countdown {
+    /// Counts a process which ended, waking the [JoinAll] after the last one.
+    fn count(&self) {
+        if self.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            if let Some(waker) = self.waker.lock().unwrap().take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Returns whether all the processes ended, registering the waker otherwise.
+    fn poll_done(&self, cx: &mut Context) -> bool {
+        if self.remaining.load(Ordering::Acquire) == 0 {
+            return true;
+        }
+
+        *self.waker.lock().unwrap() = Some(cx.waker().clone());
+
+        // The last process could have ended before the waker was registered.
+        self.remaining.load(Ordering::Acquire) == 0
+    }
+}
+
+impl Race {
+    /// Makes the process with the given index the winner, unless there is one already, and
+    /// cancels the other ones.
+    fn finish(&self, index: usize) {
+        if self
+            .winner
+            .compare_exchange(NO_WINNER, index, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return;
+        }
+
+        let members = mem::take(&mut *self.members.lock().unwrap());
+        for (member, proc) in members.iter().enumerate() {
+            if member != index {
+                proc.cancel("lost select");
+            }
+        }
+
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+
+    /// Returns the winner, registering the waker if there is none yet.
+    fn poll_winner(&self, cx: &mut Context) -> Option<usize> {
+        let winner = self.winner.load(Ordering::Acquire);
+        if winner != NO_WINNER {
+            return Some(winner);
+        }
+
+        *self.waker.lock().unwrap() = Some(cx.waker().clone());
+
+        // The winner could have ended before the waker was registered.
+        match self.winner.load(Ordering::Acquire) {
+            NO_WINNER => None,
+            winner => Some(winner),
+        }
+    }
+}
+
+impl<H> Future for JoinAll<H>
+where
+    H: Future + Unpin,
+{
+    type Output = Vec<H::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if !this.countdown.poll_done(cx) {
+            return Poll::Pending;
+        }
+
+        let mut pending = false;
+
+        for (slot, output) in this.handles.iter_mut().zip(this.outputs.iter_mut()) {
+            if let Some(handle) = slot {
+                match Pin::new(handle).poll(cx) {
+                    Poll::Ready(out) => {
+                        *output = Some(out);
+                        // Release the handle as soon as its process ended.
+                        *slot = None;
+                    }
+                    Poll::Pending => pending = true,
+                }
+            }
+        }
+
+        if pending {
+            return Poll::Pending;
+        }
+
+        let outputs = this.outputs.drain(..).map(Option::unwrap).collect();
+        Poll::Ready(outputs)
+    }
+}
+
+impl<H> Future for Select<H>
+where
+    H: Future + Unpin,
+{
+    type Output = (usize, H::Output);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        match this.race.poll_winner(cx) {
+            Some(winner) => Pin::new(&mut this.handles[winner])
+                .poll(cx)
+                .map(|output| (winner, output)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<H, R> Future for Timeout<H>
+where
+    H: Future<Output = Option<R>> + Unpin + Cancellable,
+{
+    type Output = Option<R>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = Pin::new(&mut self.handle).poll(cx) {
+            return Poll::Ready(output);
+        }
+
+        match Pin::new(&mut self.delay).poll(cx) {
+            Poll::Ready(()) => {
+                self.handle.cancel_with("timed out");
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<H> Drop for Select<H> {
+    fn drop(&mut self) {
+        // The processes keep the race alive until they end, don't let it keep them allocated.
+        mem::take(&mut *self.race.members.lock().unwrap());
+    }
+}
+
+impl<H> Debug for JoinAll<H>
+where
+    H: Future + Debug,
+{
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("JoinAll")
+            .field("handles", &self.handles)
+            .finish()
+    }
+}
+
+impl<H> Debug for Select<H>
+where
+    H: Debug,
+{
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Select")
+            .field("handles", &self.handles)
+            .finish()
+    }
+}
+
+impl<H> Debug for Timeout<H>
+where
+    H: Debug,
+{
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Timeout")
+            .field("handle", &self.handle)
+            .finish()
+    }
+}
+
+/// Returns the data of the process of the handle.
+fn pdata<H>(handle: &H) -> &ProcData
+where
+    H: Cancellable,
+{
+    unsafe { &*(handle.raw_proc().as_ptr() as *const ProcData) }
+}
@@ -0,0 +1,38 @@
+//! Tracking of the process that is being polled on the current thread.
+use crate::proc_stack::ProcStack;
+use std::cell::Cell;
+use std::ptr;
+
+thread_local! {
+    /// Stack of the process that is being polled on this thread.
+    static CURRENT: Cell<*const ProcStack> = const { Cell::new(ptr::null()) };
+}
+
+/// Calls the given function with the stack of the process that is being polled on the current
+/// thread, if any.
+pub(crate) fn with<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(&ProcStack) -> R,
+{
+    // The stack outlives the poll, which is the only time it is set as the current one.
+    match CURRENT.try_with(|current| unsafe { current.get().as_ref().map(f) }) {
+        Ok(Some(val)) => Some(val),
+        Ok(None) | Err(_) => None,
+    }
+}
+
+/// Marks the process with the given stack as the current one of the thread until dropped.
+pub(crate) struct Current(*const ProcStack);
+
+impl Current {
+    pub(crate) fn enter(stack: *const ProcStack) -> Self {
+        Current(CURRENT.with(|current| current.replace(stack)))
+    }
+}
+
+impl Drop for Current {
+    fn drop(&mut self) {
+        let previous = self.0;
+        CURRENT.with(|current| current.set(previous));
+    }
+}
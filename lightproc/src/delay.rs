@@ -0,0 +1,215 @@
+//! Waiting in processes, on the timer of their stack or on the real time.
+//!
+//! Real time waits share a single timer thread, which keeps the wakers of the pending delays
+//! by their deadline. The thread exits once it has had no delays to wait for during a second,
+//! and is started again by the next delay.
+use crate::timer::Timer;
+use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long the timer thread waits for a new delay before it exits.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    static ref TIMERS: Timers = Timers::default();
+}
+
+/// Resolves once the given time passed.
+pub(crate) enum Delay {
+    /// Waits on the real time.
+    Real {
+        deadline: Instant,
+        /// Key of the registered waker, if any.
+        key: Option<(Instant, u64)>,
+    },
+    /// Waits on the timer of the stack.
+    Timer(Pin<Box<dyn Future<Output = ()> + Send>>),
+}
+
+#[derive(Default)]
+struct Timers {
+    state: Mutex<State>,
+    /// Signaled when a delay with an earlier deadline is registered.
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    /// Wakers of the pending delays, by their deadline and registration order.
+    wakers: BTreeMap<(Instant, u64), Waker>,
+    /// Registration order of the next delay.
+    next: u64,
+    /// Whether the timer thread is running.
+    started: bool,
+    /// Whether the timer thread couldn't be started.
+    failed: bool,
+}
+
+impl Delay {
+    pub(crate) fn new(duration: Duration, timer: Option<&Arc<dyn Timer>>) -> Self {
+        match timer {
+            Some(timer) => Delay::Timer(timer.sleep(duration)),
+            None => Delay::Real {
+                deadline: Instant::now() + duration,
+                key: None,
+            },
+        }
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        match &mut *self {
+            Delay::Timer(sleep) => sleep.as_mut().poll(cx),
+            Delay::Real { deadline, key } => {
+                if Instant::now() >= *deadline {
+                    if let Some(key) = key.take() {
+                        TIMERS.remove(key);
+                    }
+
+                    return Poll::Ready(());
+                }
+
+                match TIMERS.register(*deadline, key.take(), cx.waker()) {
+                    Some(registered) => *key = Some(registered),
+                    // Without a timer thread, poll again until the deadline.
+                    None => cx.waker().wake_by_ref(),
+                }
+
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        if let Delay::Real { key: Some(key), .. } = self {
+            TIMERS.remove(*key);
+        }
+    }
+}
+
+impl Timers {
+    /// Registers the waker of a delay with the given deadline, replacing its previous one.
+    ///
+    /// Returns `None` if the timer thread can't be started.
+    fn register(
+        &'static self,
+        deadline: Instant,
+        previous: Option<(Instant, u64)>,
+        waker: &Waker,
+    ) -> Option<(Instant, u64)> {
+        let mut state = self.state.lock().unwrap();
+        if !state.start(self) {
+            return None;
+        }
+
+        let key = match previous {
+            Some(key) if state.wakers.contains_key(&key) => key,
+            _ => {
+                let key = (deadline, state.next);
+                state.next += 1;
+                key
+            }
+        };
+
+        let earliest = state.wakers.keys().next().is_none_or(|first| key < *first);
+        state.wakers.insert(key, waker.clone());
+
+        if earliest {
+            self.changed.notify_one();
+        }
+
+        Some(key)
+    }
+
+    fn remove(&self, key: (Instant, u64)) {
+        self.state.lock().unwrap().wakers.remove(&key);
+    }
+
+    /// Wakes the delays when their deadline comes, until there are none left for a while.
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            let now = Instant::now();
+            let later = state.wakers.split_off(&(now, u64::MAX));
+            let expired = std::mem::replace(&mut state.wakers, later);
+
+            if !expired.is_empty() {
+                drop(state);
+                for waker in expired.into_values() {
+                    waker.wake();
+                }
+                state = self.state.lock().unwrap();
+                continue;
+            }
+
+            state = match state.wakers.keys().next() {
+                Some((deadline, _)) => {
+                    let timeout = deadline.saturating_duration_since(now);
+                    self.changed.wait_timeout(state, timeout).unwrap().0
+                }
+                None => {
+                    let (mut state, waited) =
+                        self.changed.wait_timeout(state, IDLE_TIMEOUT).unwrap();
+
+                    // Next delay starts the thread again.
+                    if waited.timed_out() && state.wakers.is_empty() {
+                        state.started = false;
+                        return;
+                    }
+
+                    state
+                }
+            };
+        }
+    }
+}
+
+impl State {
+    /// Starts the timer thread unless it is running, returns whether it is.
+    fn start(&mut self, timers: &'static Timers) -> bool {
+        if !self.started && !self.failed {
+            let spawned = thread::Builder::new()
+                .name("lightproc-timer".to_string())
+                .spawn(move || timers.run());
+
+            self.started = spawned.is_ok();
+            self.failed = spawned.is_err();
+        }
+
+        self.started
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor;
+
+    #[test]
+    fn idle_timer_thread_exits() {
+        executor::block_on(Delay::new(Duration::from_millis(10), None));
+        assert!(TIMERS.state.lock().unwrap().started);
+
+        let deadline = Instant::now() + IDLE_TIMEOUT * 5;
+        while TIMERS.state.lock().unwrap().started {
+            assert!(Instant::now() < deadline, "timer thread is still running");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // Thread is started again for the next delay.
+        executor::block_on(Delay::new(Duration::from_millis(10), None));
+        assert!(TIMERS.state.lock().unwrap().started);
+    }
+}
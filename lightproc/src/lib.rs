@@ -4,7 +4,7 @@
 //!
 //! Beneath the implementation:
 //! * It uses futures with lifecycle callbacks to implement Erlang like processes.
-//! * Contains basic pid(process id) to identify processes.
+//! * Every process gets a unique pid(process id) to identify it, and knows its parent's pid.
 //! * All panics inside futures are propagated to upper layers.
 //!
 //! The naming convention of this crate comes from [Erlang's Lightweight Processes].
@@ -19,25 +19,42 @@
 #![allow(clippy::cast_ptr_alignment)]
 
 mod catch_unwind;
+mod current;
+mod delay;
 mod layout_helpers;
 mod proc_data;
 mod proc_ext;
+mod proc_group;
 mod proc_layout;
+mod proc_ref;
 mod proc_vtable;
 mod raw_proc;
+mod slab;
 mod state;
 
+pub mod combinators;
 pub mod lightproc;
+pub mod local;
+pub mod panic_info;
 pub mod proc_handle;
+pub mod proc_metrics;
 pub mod proc_stack;
 pub mod recoverable_handle;
+pub mod registry;
+pub mod restart_policy;
+pub mod timer;
 
 /// The lightproc prelude.
 ///
 /// The prelude re-exports lightproc structs and handles from this crate.
 pub mod prelude {
+    pub use crate::combinators::*;
     pub use crate::lightproc::*;
+    pub use crate::panic_info::*;
     pub use crate::proc_handle::*;
+    pub use crate::proc_metrics::*;
     pub use crate::proc_stack::*;
     pub use crate::recoverable_handle::*;
+    pub use crate::restart_policy::*;
+    pub use crate::timer::*;
 }
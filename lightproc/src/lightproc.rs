@@ -34,18 +34,24 @@
 //! );
 //! ```
 
+use crate::current;
+use crate::panic_info::{self, PanicInfo};
 use crate::proc_data::ProcData;
 use crate::proc_ext::ProcFutureExt;
 use crate::proc_handle::ProcHandle;
+use crate::proc_metrics::ProcMetrics;
 use crate::proc_stack::*;
 use crate::raw_proc::RawProc;
 use crate::recoverable_handle::RecoverableHandle;
+use crate::restart_policy::RestartPolicy;
 use std::fmt::{self, Debug, Formatter};
 use std::future::Future;
 use std::marker::PhantomData;
 use std::mem;
 use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
 use std::ptr::NonNull;
+use std::task::{Context, Poll};
 
 /// Struct to create and operate lightweight processes
 pub struct LightProc {
@@ -56,6 +62,14 @@ pub struct LightProc {
 unsafe impl Send for LightProc {}
 unsafe impl Sync for LightProc {}
 
+/// Future of a process, surrounded with the async setup and teardown of its stack if it has any.
+enum Lifecycle<F, R> {
+    /// Future of a stack without setup and teardown, polled as it is.
+    Plain(F),
+    /// Future awaited between the setup and the teardown.
+    Wrapped(Pin<Box<dyn Future<Output = R> + Send>>),
+}
+
 impl LightProc {
     ///
     /// Creates a recoverable process which will signal occurred
@@ -97,8 +111,90 @@ impl LightProc {
         R: Send + 'static,
         S: Fn(LightProc) + Send + Sync + 'static,
     {
-        let recovery_future = AssertUnwindSafe(future).catch_unwind();
-        let (proc, handle) = Self::build(recovery_future, schedule, stack);
+        panic_info::install_hook();
+
+        let future = Self::with_lifecycle(future, &stack);
+        let recovery_future = Self::close_group_on_panic(AssertUnwindSafe(future).catch_unwind());
+        let (proc, handle) = Self::allocate(recovery_future, schedule, stack);
+        (proc, RecoverableHandle(handle))
+    }
+
+    ///
+    /// Creates a recoverable process which builds its future with the given factory, and
+    /// rebuilds it when it panics, as the given policy allows.
+    ///
+    /// The setup and teardown of the stack run once, around all the attempts. The handle
+    /// resolves when an attempt completes, or with the last panic once the process gives up.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use lightproc::prelude::*;
+    /// # use std::sync::atomic::{AtomicUsize, Ordering};
+    /// # use std::sync::Arc;
+    /// #
+    /// # // ... basic schedule function with no waker logic
+    /// # fn schedule_function(proc: LightProc) { proc.run(); }
+    /// #
+    /// let attempts = Arc::new(AtomicUsize::new(0));
+    ///
+    /// // ... creating a process which succeeds on its third attempt
+    /// let (proc, handle) = LightProc::restartable(
+    ///     move || {
+    ///         let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+    ///         async move {
+    ///             assert!(attempt >= 2, "not yet");
+    ///             attempt
+    ///         }
+    ///     },
+    ///     schedule_function,
+    ///     ProcStack::default(),
+    ///     RestartPolicy::default(),
+    /// );
+    /// proc.run();
+    ///
+    /// assert_eq!(futures::executor::block_on(handle), Some(2));
+    /// ```
+    pub fn restartable<T, F, R, S>(
+        factory: T,
+        schedule: S,
+        stack: ProcStack,
+        policy: RestartPolicy,
+    ) -> (LightProc, RecoverableHandle<R>)
+    where
+        T: Fn() -> F + Send + Sync + 'static,
+        F: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+        S: Fn(LightProc) + Send + Sync + 'static,
+    {
+        panic_info::install_hook();
+
+        let attempts = async move {
+            let mut restart = 0;
+
+            loop {
+                match AssertUnwindSafe(async { factory().await })
+                    .catch_unwind()
+                    .await
+                {
+                    Ok(output) => return Ok(output),
+                    Err(info) if restart >= policy.max_restarts() => return Err(info),
+                    Err(_) => {
+                        let timer = current::with(|stack| stack.timer.clone()).flatten();
+                        policy.delay(restart, timer.as_ref()).await;
+                        restart += 1;
+                    }
+                }
+            }
+        };
+
+        let future = Self::with_lifecycle(attempts, &stack);
+        let recovery_future = Self::close_group_on_panic(async move {
+            match AssertUnwindSafe(future).catch_unwind().await {
+                Ok(result) => result,
+                Err(info) => Err(info),
+            }
+        });
+        let (proc, handle) = Self::allocate(recovery_future, schedule, stack);
         (proc, RecoverableHandle(handle))
     }
 
@@ -132,6 +228,60 @@ impl LightProc {
     /// );
     /// ```
     pub fn build<F, R, S>(future: F, schedule: S, stack: ProcStack) -> (LightProc, ProcHandle<R>)
+    where
+        F: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+        S: Fn(LightProc) + Send + Sync + 'static,
+    {
+        let future = Self::with_lifecycle(future, &stack);
+        Self::allocate(future, schedule, stack)
+    }
+
+    /// Closes the group of the recoverable process when it resolves with a panic, as it is
+    /// closed when a standard process panics.
+    async fn close_group_on_panic<F, R>(future: F) -> Result<R, PanicInfo>
+    where
+        F: Future<Output = Result<R, PanicInfo>> + Send + 'static,
+        R: Send + 'static,
+    {
+        let result = future.await;
+
+        if result.is_err() {
+            current::with(|stack| stack.group.close("parent panicked"));
+        }
+
+        result
+    }
+
+    /// Surrounds the future with the async setup and teardown of the stack, if it has any.
+    fn with_lifecycle<F, R>(future: F, stack: &ProcStack) -> Lifecycle<F, R>
+    where
+        F: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+    {
+        if stack.setup.is_none() && stack.teardown.is_none() {
+            return Lifecycle::Plain(future);
+        }
+
+        let setup = stack.setup.clone();
+        let teardown = stack.teardown.clone();
+
+        Lifecycle::Wrapped(Box::pin(async move {
+            if let Some(setup) = setup {
+                setup().await;
+            }
+
+            let output = future.await;
+
+            if let Some(teardown) = teardown {
+                teardown().await;
+            }
+
+            output
+        }))
+    }
+
+    fn allocate<F, R, S>(future: F, schedule: S, stack: ProcStack) -> (LightProc, ProcHandle<R>)
     where
         F: Future<Output = R> + Send + 'static,
         R: Send + 'static,
@@ -141,6 +291,7 @@ impl LightProc {
         let proc = LightProc { raw_proc: raw_proc };
         let handle = ProcHandle {
             raw_proc: raw_proc,
+            abort_on_drop: false,
             _marker: PhantomData,
         };
         (proc, handle)
@@ -181,6 +332,32 @@ impl LightProc {
         }
     }
 
+    ///
+    /// Cancel the lightproc with the given reason, which is passed to the `after_cancel`
+    /// callback and to the handle of the proc.
+    ///
+    /// If the proc has already been cancelled, the first reason is kept.
+    pub fn cancel_with<T>(&self, reason: T)
+    where
+        T: Into<String>,
+    {
+        let ptr = self.raw_proc.as_ptr();
+        let pdata = ptr as *const ProcData;
+
+        unsafe {
+            (*pdata).set_reason(reason.into());
+            (*pdata).cancel();
+        }
+    }
+
+    ///
+    /// Gives the poll accounting of the lightproc so far.
+    pub fn metrics(&self) -> ProcMetrics {
+        let pdata = self.raw_proc.as_ptr() as *const ProcData;
+
+        unsafe { (*pdata).metrics.snapshot() }
+    }
+
     ///
     /// Gives a reference to given [ProcStack] when building the light proc.
     pub fn stack(&self) -> &ProcStack {
@@ -194,6 +371,23 @@ impl LightProc {
     }
 }
 
+impl<F, R> Future for Lifecycle<F, R>
+where
+    F: Future<Output = R>,
+{
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<R> {
+        // The plain future is never moved out of the pinned enum, the wrapped one is boxed.
+        unsafe {
+            match self.get_unchecked_mut() {
+                Lifecycle::Plain(future) => Pin::new_unchecked(future).poll(cx),
+                Lifecycle::Wrapped(future) => future.as_mut().poll(cx),
+            }
+        }
+    }
+}
+
 impl Debug for LightProc {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
         let ptr = self.raw_proc.as_ptr();
@@ -217,6 +411,7 @@ impl Drop for LightProc {
 
             // Drop the future.
             ((*pdata).vtable.drop_future)(ptr);
+            (*pdata).cancelled();
 
             // Drop the proc reference.
             ((*pdata).vtable.decrement)(ptr);
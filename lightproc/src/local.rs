@@ -0,0 +1,105 @@
+//!
+//! Process local storage
+//!
+//! Every process can carry typed values in its stack, at most one of each type, which are set
+//! with [ProcStack::with_local] and can be read from inside of the process with [get] or [with],
+//! without threading them through every function.
+//!
+//! Processes built from inside of another process inherit the locals of their parent if their
+//! stack is built with [ProcStack::with_inherited_locals]. Their own locals take precedence
+//! over the inherited ones.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lightproc::local;
+//! use lightproc::prelude::*;
+//!
+//! #[derive(Clone, Debug, PartialEq)]
+//! struct TraceId(u64);
+//!
+//! let (proc, handle) = LightProc::build(
+//!     async { local::get::<TraceId>() },
+//!     |_| {},
+//!     ProcStack::default().with_local(TraceId(42)),
+//! );
+//! proc.run();
+//!
+//! assert_eq!(futures::executor::block_on(handle), Some(Some(TraceId(42))));
+//! ```
+//!
+//! [ProcStack::with_local]: ../proc_stack/struct.ProcStack.html#method.with_local
+//! [ProcStack::with_inherited_locals]: ../proc_stack/struct.ProcStack.html#method.with_inherited_locals
+//! [get]: fn.get.html
+//! [with]: fn.with.html
+use crate::current;
+use crate::proc_stack::ProcStack;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Typed values stored in the stack of a process.
+#[derive(Clone, Default)]
+pub(crate) struct Locals(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
+
+impl Locals {
+    pub(crate) fn insert<T>(&mut self, value: T)
+    where
+        T: Send + Sync + 'static,
+    {
+        self.0.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    pub(crate) fn get<T>(&self) -> Option<&T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.0
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Adds the values of the given locals whose types aren't set yet.
+    fn inherit(&mut self, parent: &Locals) {
+        for (ty, value) in parent.0.iter() {
+            self.0.entry(*ty).or_insert_with(|| value.clone());
+        }
+    }
+}
+
+///
+/// Returns a clone of the value of the given type stored in the stack of the current process.
+///
+/// Returns `None` if it isn't called from inside of a process or the process doesn't have such
+/// a value.
+pub fn get<T>() -> Option<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    with(T::clone)
+}
+
+///
+/// Calls the given function with a reference to the value of the given type stored in the stack
+/// of the current process, and returns its result.
+///
+/// Returns `None` if it isn't called from inside of a process or the process doesn't have such
+/// a value.
+pub fn with<T, F, R>(f: F) -> Option<R>
+where
+    T: Send + Sync + 'static,
+    F: FnOnce(&T) -> R,
+{
+    current::with(|stack| stack.locals.get::<T>().map(f)).flatten()
+}
+
+/// Copies the locals of the current process into the given stack if it inherits them.
+pub(crate) fn inherit(stack: &mut ProcStack) {
+    if stack.inherit_locals {
+        current::with(|parent| stack.locals.inherit(&parent.locals));
+    }
+}
@@ -0,0 +1,155 @@
+//!
+//! Information about the panics of recoverable processes
+//!
+//! When the future of a recoverable process panics, the panic is caught along with its payload
+//! and the location it happened at, and given to the `after_panic` callback of the process and
+//! to the [RecoverableHandle] as a [PanicInfo].
+//!
+//! Locations are recorded by a panic hook which is installed with the first recoverable process
+//! and calls the previously installed hook. If it is replaced afterwards, locations aren't
+//! available.
+//!
+//! [RecoverableHandle]: ../recoverable_handle/struct.RecoverableHandle.html
+//! [PanicInfo]: struct.PanicInfo.html
+use crate::current;
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::panic;
+use std::sync::Once;
+
+thread_local! {
+    /// Location of the last panic of a process on this thread.
+    static LOCATION: RefCell<Option<PanicLocation>> = const { RefCell::new(None) };
+}
+
+///
+/// Information about a panic which happened in a recoverable process.
+pub struct PanicInfo {
+    payload: Box<dyn Any + Send>,
+    location: Option<PanicLocation>,
+}
+
+///
+/// Location of the source code which a process panicked at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PanicLocation {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+impl PanicInfo {
+    pub(crate) fn new(payload: Box<dyn Any + Send>) -> Self {
+        let location = LOCATION
+            .try_with(|location| location.borrow_mut().take())
+            .unwrap_or(None);
+
+        PanicInfo { payload, location }
+    }
+
+    ///
+    /// Message of the panic, if its payload is a string, as it is with `panic!`.
+    pub fn message(&self) -> Option<&str> {
+        if let Some(message) = self.payload.downcast_ref::<&'static str>() {
+            Some(message)
+        } else if let Some(message) = self.payload.downcast_ref::<String>() {
+            Some(message)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Location of the panic, if the panic hook of the processes recorded it.
+    pub fn location(&self) -> Option<&PanicLocation> {
+        self.location.as_ref()
+    }
+
+    ///
+    /// Payload of the panic.
+    pub fn payload(&self) -> &(dyn Any + Send) {
+        &*self.payload
+    }
+
+    ///
+    /// Takes the payload of the panic, e.g. to resume unwinding with it.
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+}
+
+impl PanicLocation {
+    ///
+    /// Name of the source file.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    ///
+    /// Line in the source file.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    ///
+    /// Column in the line.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+/// Installs the panic hook recording the locations of the panics of processes, once.
+pub(crate) fn install_hook() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            if current::with(|_| ()).is_some() {
+                let location = info.location().map(|location| PanicLocation {
+                    file: location.file().to_string(),
+                    line: location.line(),
+                    column: location.column(),
+                });
+
+                // The thread can be shutting down already.
+                LOCATION
+                    .try_with(|current| *current.borrow_mut() = location)
+                    .ok();
+            }
+
+            previous(info);
+        }));
+    });
+}
+
+impl Display for PanicInfo {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self.message() {
+            Some(message) => write!(fmt, "panicked at '{}'", message)?,
+            None => write!(fmt, "panicked")?,
+        }
+
+        match &self.location {
+            Some(location) => write!(fmt, ", {}", location),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Debug for PanicInfo {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("PanicInfo")
+            .field("message", &self.message())
+            .field("location", &self.location)
+            .finish()
+    }
+}
+
+impl Display for PanicLocation {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::layout_helpers::extend;
+use crate::proc_metrics::Counters;
 use crate::proc_stack::ProcStack;
 use crate::proc_vtable::ProcVTable;
 use crate::state::*;
@@ -6,9 +7,15 @@ use crossbeam_utils::Backoff;
 use std::alloc::Layout;
 use std::cell::Cell;
 use std::fmt::{self, Debug, Formatter};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{self, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::task::Waker;
 
+/// Callbacks which run once the proc completes or is closed.
+pub(crate) type Listeners = Mutex<Vec<Box<dyn FnOnce() + Send>>>;
+
 /// The pdata of a proc.
 ///
 /// This pdata is stored right at the beginning of every heap-allocated proc.
@@ -28,6 +35,19 @@ pub(crate) struct ProcData {
     /// In addition to the actual waker virtual table, it also contains pointers to several other
     /// methods necessary for bookkeeping the heap-allocated proc.
     pub(crate) vtable: &'static ProcVTable,
+
+    /// The reason the proc was cancelled with, if any.
+    ///
+    /// It is set at most once and lives until the proc is destroyed.
+    pub(crate) reason: AtomicPtr<String>,
+
+    /// Poll accounting of the proc.
+    pub(crate) metrics: Counters,
+
+    /// Callbacks to run once the proc ends, registered by the combinators of its handles.
+    ///
+    /// They are allocated with the first callback, and live until the proc is destroyed.
+    pub(crate) listeners: AtomicPtr<Listeners>,
 }
 
 impl ProcData {
@@ -57,6 +77,53 @@ impl ProcData {
                         self.notify();
                     }
 
+                    self.ended();
+                    break;
+                }
+                Err(s) => state = s,
+            }
+        }
+    }
+
+    /// Cancels the proc from outside of it, like its handle does.
+    ///
+    /// If the proc is neither scheduled nor running, it gets scheduled so that its future is
+    /// dropped by the executor.
+    pub(crate) fn cancel_and_schedule(&self) {
+        let ptr = self as *const ProcData as *const ();
+        let mut state = self.state.load(Ordering::Acquire);
+
+        loop {
+            // If the proc has been completed or closed, it can't be cancelled.
+            if state & (COMPLETED | CLOSED) != 0 {
+                break;
+            }
+
+            // If the proc is not scheduled nor running, we'll need to schedule it.
+            let new = if state & (SCHEDULED | RUNNING) == 0 {
+                (state | SCHEDULED | CLOSED) + REFERENCE
+            } else {
+                state | CLOSED
+            };
+
+            // Mark the proc as closed.
+            match self
+                .state
+                .compare_exchange_weak(state, new, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    // If the proc is not scheduled nor running, schedule it so that its future
+                    // gets dropped by the executor.
+                    if state & (SCHEDULED | RUNNING) == 0 {
+                        unsafe { (self.vtable.schedule)(ptr) };
+                    }
+
+                    // Notify the awaiter that the proc has been closed.
+                    if state & AWAITER != 0 {
+                        self.notify();
+                    }
+
+                    self.ended();
                     break;
                 }
                 Err(s) => state = s,
@@ -64,6 +131,117 @@ impl ProcData {
         }
     }
 
+    /// Registers a callback to run once the proc completes or is closed, or runs it right away
+    /// if the proc already ended.
+    pub(crate) fn on_end(&self, callback: Box<dyn FnOnce() + Send>) {
+        let mut listeners = self.listeners.load(Ordering::Acquire);
+
+        if listeners.is_null() {
+            let new = Box::into_raw(Box::new(Listeners::default()));
+
+            listeners = match self.listeners.compare_exchange(
+                ptr::null_mut(),
+                new,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => new,
+                Err(existing) => {
+                    unsafe { drop(Box::from_raw(new)) };
+                    existing
+                }
+            };
+        }
+
+        unsafe { (*listeners).lock().unwrap().push(callback) };
+
+        // The proc could have ended, and run its callbacks, before this one was registered.
+        atomic::fence(Ordering::SeqCst);
+        if self.state.load(Ordering::Acquire) & (COMPLETED | CLOSED) != 0 {
+            self.ended();
+        }
+    }
+
+    /// Runs the callbacks registered to run once the proc ends, after it completed or was
+    /// closed.
+    pub(crate) fn ended(&self) {
+        // Pairs with the fence of `on_end`, so that either side sees the other one.
+        atomic::fence(Ordering::SeqCst);
+
+        let listeners = self.listeners.load(Ordering::Acquire);
+        if listeners.is_null() {
+            return;
+        }
+
+        let callbacks = unsafe { mem::take(&mut *(*listeners).lock().unwrap()) };
+        for callback in callbacks {
+            callback();
+        }
+    }
+
+    /// Drops the callbacks registered to run once the proc ends, if any.
+    pub(crate) fn drop_listeners(&self) {
+        let listeners = self.listeners.swap(ptr::null_mut(), Ordering::AcqRel);
+        if !listeners.is_null() {
+            unsafe { drop(Box::from_raw(listeners)) };
+        }
+    }
+
+    /// Sets the reason of the cancellation, unless the proc can't be cancelled anymore or
+    /// already has a reason.
+    pub(crate) fn set_reason(&self, reason: String) {
+        if self.state.load(Ordering::Acquire) & (COMPLETED | CLOSED) != 0 {
+            return;
+        }
+
+        let reason = Box::into_raw(Box::new(reason));
+        if self
+            .reason
+            .compare_exchange(ptr::null_mut(), reason, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            unsafe { drop(Box::from_raw(reason)) };
+        }
+    }
+
+    /// Returns the reason of the cancellation, if any.
+    pub(crate) fn reason(&self) -> Option<&str> {
+        unsafe {
+            self.reason
+                .load(Ordering::Acquire)
+                .as_ref()
+                .map(String::as_str)
+        }
+    }
+
+    /// Drops the reason of the cancellation, if any.
+    pub(crate) fn drop_reason(&self) {
+        let reason = self.reason.swap(ptr::null_mut(), Ordering::AcqRel);
+        if !reason.is_null() {
+            unsafe { drop(Box::from_raw(reason)) };
+        }
+    }
+
+    /// Calls the `after_cancel` callback of the proc and cancels its group, after its future
+    /// was dropped without completing.
+    pub(crate) fn cancelled(&self) {
+        let stack = self.stack();
+
+        if let Some(after_cancel_cb) = &stack.after_cancel {
+            (*after_cancel_cb.clone())(self.reason());
+        }
+
+        stack.group.close("parent cancelled");
+    }
+
+    /// Returns the stack stored after the pdata.
+    pub(crate) fn stack(&self) -> &ProcStack {
+        unsafe {
+            let ptr = (self as *const ProcData as *const u8).add(Self::offset_stack());
+            &*(ptr as *const ProcStack)
+        }
+    }
+
     /// Notifies the proc blocked on the proc.
     ///
     /// If there is a registered waker, it will be removed from the pdata and woken.
@@ -0,0 +1,84 @@
+//! Groups of the processes which are cancelled along with the process they were built from.
+use crate::proc_data::ProcData;
+use crate::proc_ref::ProcRef;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex};
+
+/// Processes which are cancelled when the process owning the group ends.
+#[derive(Default)]
+pub(crate) struct ProcGroup {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Members of the group which didn't end yet, by the address of their process.
+    members: HashMap<usize, ProcRef>,
+    /// Reason the group was closed with, once the owning process ended.
+    closed: Option<&'static str>,
+}
+
+impl ProcGroup {
+    /// Adds the given process to the group, or cancels it if the group is already closed.
+    pub(crate) fn join(group: &Arc<ProcGroup>, ptr: NonNull<()>) {
+        let member = ProcRef::new(ptr);
+        let key = ptr.as_ptr() as usize;
+
+        {
+            let mut inner = group.inner.lock().unwrap();
+            if let Some(reason) = inner.closed {
+                drop(inner);
+                member.cancel(reason);
+                return;
+            }
+
+            inner.members.insert(key, member);
+        }
+
+        // Members leave as soon as they end, so that long living processes don't keep them
+        // allocated. The listener runs right away if the member already ended.
+        let group = Arc::downgrade(group);
+        let pdata = unsafe { &*(ptr.as_ptr() as *const ProcData) };
+        pdata.on_end(Box::new(move || {
+            if let Some(group) = group.upgrade() {
+                group.leave(key);
+            }
+        }));
+    }
+
+    /// Removes the member with the given key, releasing it outside of the lock.
+    fn leave(&self, key: usize) {
+        let member = self.inner.lock().unwrap().members.remove(&key);
+        drop(member);
+    }
+
+    /// Cancels the members of the group with the given reason, and the ones joining later.
+    pub(crate) fn close(&self, reason: &'static str) {
+        let members = {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.closed.is_some() {
+                return;
+            }
+
+            inner.closed = Some(reason);
+            std::mem::take(&mut inner.members)
+        };
+
+        for member in members.into_values() {
+            member.cancel(reason);
+        }
+    }
+}
+
+impl Debug for ProcGroup {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let inner = self.inner.lock().unwrap();
+
+        fmt.debug_struct("ProcGroup")
+            .field("members", &inner.members.len())
+            .field("closed", &inner.closed)
+            .finish()
+    }
+}
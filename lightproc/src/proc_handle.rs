@@ -1,7 +1,9 @@
 //!
 //! Handle for tasks which don't need to unwind panics inside
 //! the given futures.
+use crate::combinators::{sealed, Cancellable, JoinAll, Select, Timeout};
 use crate::proc_data::ProcData;
+use crate::proc_metrics::ProcMetrics;
 use crate::proc_stack::ProcStack;
 use crate::state::*;
 use std::fmt::{self, Debug, Formatter};
@@ -11,6 +13,7 @@ use std::pin::Pin;
 use std::ptr::NonNull;
 use std::sync::atomic::Ordering;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 /// A handle that awaits the result of a proc.
 ///
@@ -18,10 +21,17 @@ use std::task::{Context, Poll};
 ///
 /// * `None` indicates the proc has panicked or was cancelled
 /// * `Some(res)` indicates the proc has completed with `res`
+///
+/// Dropping the handle detaches the proc, unless the handle is in the [abort_on_drop] mode.
+///
+/// [abort_on_drop]: #method.abort_on_drop
 pub struct ProcHandle<R> {
     /// A raw proc pointer.
     pub(crate) raw_proc: NonNull<()>,
 
+    /// Whether dropping the handle cancels the proc.
+    pub(crate) abort_on_drop: bool,
+
     /// A marker capturing the generic type `R`.
     pub(crate) _marker: PhantomData<R>,
 }
@@ -38,50 +48,80 @@ impl<R> ProcHandle<R> {
     ///
     /// When a proc is cancelled, its future cannot be polled again and will be dropped instead.
     pub fn cancel(&self) {
-        let ptr = self.raw_proc.as_ptr();
-        let pdata = ptr as *const ProcData;
+        let pdata = self.raw_proc.as_ptr() as *const ProcData;
 
         unsafe {
-            let mut state = (*pdata).state.load(Ordering::Acquire);
+            (*pdata).cancel_and_schedule();
+        }
+    }
 
-            loop {
-                // If the proc has been completed or closed, it can't be cancelled.
-                if state & (COMPLETED | CLOSED) != 0 {
-                    break;
-                }
+    /// Cancels the proc with the given reason, which is passed to the `after_cancel` callback.
+    ///
+    /// If the proc has already been cancelled, the first reason is kept.
+    pub fn cancel_with<T>(&self, reason: T)
+    where
+        T: Into<String>,
+    {
+        let pdata = self.raw_proc.as_ptr() as *const ProcData;
 
-                // If the proc is not scheduled nor running, we'll need to schedule it.
-                let new = if state & (SCHEDULED | RUNNING) == 0 {
-                    (state | SCHEDULED | CLOSED) + REFERENCE
-                } else {
-                    state | CLOSED
-                };
+        unsafe {
+            (*pdata).set_reason(reason.into());
+        }
+        self.cancel();
+    }
 
-                // Mark the proc as closed.
-                match (*pdata).state.compare_exchange_weak(
-                    state,
-                    new,
-                    Ordering::AcqRel,
-                    Ordering::Acquire,
-                ) {
-                    Ok(_) => {
-                        // If the proc is not scheduled nor running, schedule it so that its future
-                        // gets dropped by the executor.
-                        if state & (SCHEDULED | RUNNING) == 0 {
-                            ((*pdata).vtable.schedule)(ptr);
-                        }
+    /// Returns the reason the proc was cancelled with, if any.
+    pub fn cancel_reason(&self) -> Option<&str> {
+        let pdata = self.raw_proc.as_ptr() as *const ProcData;
 
-                        // Notify the awaiter that the proc has been closed.
-                        if state & AWAITER != 0 {
-                            (*pdata).notify();
-                        }
+        unsafe { (*pdata).reason() }
+    }
 
-                        break;
-                    }
-                    Err(s) => state = s,
-                }
-            }
-        }
+    /// Returns the poll accounting of the proc so far.
+    pub fn metrics(&self) -> ProcMetrics {
+        let pdata = self.raw_proc.as_ptr() as *const ProcData;
+
+        unsafe { (*pdata).metrics.snapshot() }
+    }
+
+    /// Lets the proc run to completion on its own, dropping the handle.
+    ///
+    /// This is what dropping the handle does, unless it is in the [abort_on_drop] mode.
+    ///
+    /// [abort_on_drop]: #method.abort_on_drop
+    pub fn detach(mut self) {
+        self.abort_on_drop = false;
+    }
+
+    /// Makes dropping the handle cancel the proc, unless it was [detached].
+    ///
+    /// [detached]: #method.detach
+    pub fn abort_on_drop(mut self) -> Self {
+        self.abort_on_drop = true;
+        self
+    }
+
+    /// Resolves to the output of the proc, or cancels it with the "timed out" reason and resolves
+    /// to `None` if it doesn't complete in the given time.
+    pub fn with_timeout(self, timeout: Duration) -> Timeout<Self> {
+        Timeout::new(self, timeout)
+    }
+
+    /// Resolves to the outputs of all the given procs, in their order.
+    pub fn join_all<I>(handles: I) -> JoinAll<Self>
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        JoinAll::new(handles)
+    }
+
+    /// Resolves to the index and the output of the first of the given procs which ends,
+    /// cancelling the other ones with the "lost select" reason.
+    pub fn select<I>(handles: I) -> Select<Self>
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        Select::new(handles)
     }
 
     /// Returns a reference to the stack stored inside the proc.
@@ -164,6 +204,22 @@ impl<R> Future for ProcHandle<R> {
     }
 }
 
+impl<R> Cancellable for ProcHandle<R> {
+    fn cancel_with(&self, reason: &str) {
+        ProcHandle::cancel_with(self, reason)
+    }
+}
+
+impl<R> sealed::Handle for ProcHandle<R> {
+    fn raw_proc(&self) -> NonNull<()> {
+        self.raw_proc
+    }
+
+    fn stack(&self) -> &ProcStack {
+        ProcHandle::stack(self)
+    }
+}
+
 impl<R> Debug for ProcHandle<R> {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
         let ptr = self.raw_proc.as_ptr();
@@ -184,6 +240,10 @@ impl<R> Drop for ProcHandle<R> {
         // A place where the output will be stored in case it needs to be dropped.
         let mut output = None;
 
+        if self.abort_on_drop {
+            self.cancel();
+        }
+
         unsafe {
             // Optimistically assume the `ProcHandle` is being dropped just after creating the
             // proc. This is a common case so if the handle is not used, the overhead of it is only
@@ -236,6 +296,7 @@ impl<R> Drop for ProcHandle<R> {
                                 // schedule dropping its future or destroy it.
                                 if state & !(REFERENCE - 1) == 0 {
                                     if state & CLOSED == 0 {
+                                        (*pdata).ended();
                                         ((*pdata).vtable.schedule)(ptr);
                                     } else {
                                         ((*pdata).vtable.destroy)(ptr);
@@ -0,0 +1,114 @@
+//!
+//! Accounting of the time processes spend being polled
+//!
+//! Every process counts its polls and how many times it was woken up. Processes whose stack
+//! enables [ProcStack::with_poll_timing] also measure the time spent polling them and their
+//! longest poll. The figures can be read at any time through the handles of the process and the
+//! [registry], to find out which processes are eating the CPU.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lightproc::prelude::*;
+//!
+//! let stack = ProcStack::default().with_poll_timing(true);
+//! let (proc, handle) = LightProc::build(async { 1 + 1 }, |_| {}, stack);
+//! proc.run();
+//!
+//! let metrics = handle.metrics();
+//! assert_eq!(metrics.polls(), 1);
+//! assert!(metrics.longest_poll() <= metrics.poll_time());
+//! ```
+//!
+//! [ProcStack::with_poll_timing]: ../proc_stack/struct.ProcStack.html#method.with_poll_timing
+//! [registry]: ../registry/index.html
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Counters of a process, updated while it runs.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    polls: AtomicU64,
+    poll_nanos: AtomicU64,
+    longest_poll_nanos: AtomicU64,
+    wakeups: AtomicU64,
+}
+
+impl Counters {
+    /// Records a poll, which took the given time if it was measured.
+    pub(crate) fn poll(&self, elapsed: Option<Duration>) {
+        self.polls.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(elapsed) = elapsed {
+            let nanos = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+
+            self.poll_nanos.fetch_add(nanos, Ordering::Relaxed);
+            self.longest_poll_nanos.fetch_max(nanos, Ordering::Relaxed);
+        }
+    }
+
+    /// Records a wakeup.
+    pub(crate) fn wakeup(&self) {
+        self.wakeups.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> ProcMetrics {
+        ProcMetrics {
+            polls: self.polls.load(Ordering::Relaxed),
+            poll_time: Duration::from_nanos(self.poll_nanos.load(Ordering::Relaxed)),
+            longest_poll: Duration::from_nanos(self.longest_poll_nanos.load(Ordering::Relaxed)),
+            wakeups: self.wakeups.load(Ordering::Relaxed),
+        }
+    }
+}
+
+///
+/// Snapshot of the poll accounting of a process.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProcMetrics {
+    polls: u64,
+    poll_time: Duration,
+    longest_poll: Duration,
+    wakeups: u64,
+}
+
+impl ProcMetrics {
+    ///
+    /// Number of times the process was polled.
+    pub fn polls(&self) -> u64 {
+        self.polls
+    }
+
+    ///
+    /// Total time spent polling the process.
+    ///
+    /// Zero unless the stack of the process enables poll timing.
+    pub fn poll_time(&self) -> Duration {
+        self.poll_time
+    }
+
+    ///
+    /// Longest time a single poll of the process took.
+    ///
+    /// Zero unless the stack of the process enables poll timing.
+    pub fn longest_poll(&self) -> Duration {
+        self.longest_poll
+    }
+
+    ///
+    /// Number of times the process was woken up.
+    pub fn wakeups(&self) -> u64 {
+        self.wakeups
+    }
+}
+
+impl Display for ProcMetrics {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} polls in {:?} (longest {:?}), {} wakeups",
+            self.polls, self.poll_time, self.longest_poll, self.wakeups
+        )
+    }
+}
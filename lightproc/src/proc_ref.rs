@@ -0,0 +1,53 @@
+//! References to processes, which keep them allocated.
+use crate::proc_data::ProcData;
+use crate::state::*;
+use std::ptr::NonNull;
+use std::sync::atomic::Ordering;
+
+/// A reference to a process, keeping it allocated.
+pub(crate) struct ProcRef(NonNull<()>);
+
+unsafe impl Send for ProcRef {}
+unsafe impl Sync for ProcRef {}
+
+impl ProcRef {
+    pub(crate) fn new(ptr: NonNull<()>) -> Self {
+        let pdata = ptr.as_ptr() as *const ProcData;
+
+        unsafe {
+            // Increment the reference count, like a waker does.
+            let state = (*pdata).state.fetch_add(REFERENCE, Ordering::Relaxed);
+
+            // If the reference count overflowed, abort.
+            if state > isize::MAX as usize {
+                std::process::abort();
+            }
+        }
+
+        ProcRef(ptr)
+    }
+
+    pub(crate) fn pdata(&self) -> &ProcData {
+        unsafe { &*(self.0.as_ptr() as *const ProcData) }
+    }
+
+    pub(crate) fn has_ended(&self) -> bool {
+        self.pdata().state.load(Ordering::Acquire) & (COMPLETED | CLOSED) != 0
+    }
+
+    pub(crate) fn cancel(&self, reason: &str) {
+        let pdata = self.pdata();
+        pdata.set_reason(reason.to_string());
+        pdata.cancel_and_schedule();
+    }
+}
+
+impl Drop for ProcRef {
+    fn drop(&mut self) {
+        let ptr = self.0.as_ptr();
+
+        unsafe {
+            (self.pdata().vtable.decrement)(ptr);
+        }
+    }
+}
@@ -5,10 +5,25 @@
 //!
 //! If we want to make an analogy, stack abstraction is similar to actor lifecycle abstractions
 //! in frameworks like Akka, but tailored version for Rust environment.
+use crate::local::Locals;
+use crate::panic_info::PanicInfo;
+use crate::proc_group::ProcGroup;
+use crate::timer::Timer;
 use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+/// Callback executed after a panic, with the information about it
+type AfterPanic = dyn Fn(&PanicInfo) + Send + Sync;
+
+/// Callback executed after a cancellation, with its reason
+type AfterCancel = dyn Fn(Option<&str>) + Send + Sync;
+
+/// Callback creating a future which is awaited as a part of the process
+pub(crate) type AsyncCallback = dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync;
+
 /// Stack abstraction for lightweight processes
 ///
 /// # Example
@@ -26,8 +41,45 @@ pub struct ProcStack {
     /// Process ID for the Lightweight Process
     ///
     /// Can be used to identify specific processes during any executor, reactor implementations.
+    /// Unless it is set, a unique one is allocated when the process is built.
     pub pid: AtomicUsize,
 
+    /// Process ID of the process which built this one
+    ///
+    /// `0` if the process wasn't built from inside of another process.
+    pub(crate) parent_pid: usize,
+
+    /// Scheduling priority of the process
+    ///
+    /// Executors can use this to poll the processes with higher priority first.
+    pub(crate) priority: Priority,
+
+    /// Core that the process is pinned to
+    ///
+    /// Executors can use this to always run the process on the worker of the given core.
+    pub(crate) affinity: Option<CoreId>,
+
+    /// Typed values stored for the process
+    ///
+    /// Can be read from inside of the process with [local](../local/index.html).
+    pub(crate) locals: Locals,
+
+    /// Whether the process inherits the locals of the process which builds it
+    pub(crate) inherit_locals: bool,
+
+    /// Whether the process is cancelled when the process which builds it ends
+    pub(crate) scoped: bool,
+
+    /// Whether the time spent polling the process is measured
+    ///
+    /// Off by default, as it reads the clock twice in every poll.
+    pub(crate) poll_timing: bool,
+
+    /// Processes which are cancelled when this process ends
+    ///
+    /// Every stack has its own group, clones of the stack start with an empty one.
+    pub(crate) group: Arc<ProcGroup>,
+
     /// Before start callback
     ///
     /// This callback is called before we start to inner future of the process
@@ -45,12 +97,35 @@ pub struct ProcStack {
     ///
     /// This callback is only called when a panic has been occurred.
     /// Mind that [ProcHandle](proc_handle/struct.ProcHandle.html) is not using this
-    pub(crate) after_panic: Option<Arc<dyn Fn() + Send + Sync>>,
+    pub(crate) after_panic: Option<Arc<AfterPanic>>,
+
+    /// Async setup callback
+    ///
+    /// The future it creates is awaited by the process before its inner future is first polled.
+    pub(crate) setup: Option<Arc<AsyncCallback>>,
+
+    /// Async teardown callback
+    ///
+    /// The future it creates is awaited by the process after its inner future resolved.
+    pub(crate) teardown: Option<Arc<AsyncCallback>>,
+
+    /// After cancel callback
+    ///
+    /// This callback is called when the future gets dropped without being completed,
+    /// with the reason the process was cancelled with, if any.
+    pub(crate) after_cancel: Option<Arc<AfterCancel>>,
+
+    /// Clock of the process
+    ///
+    /// Restart backoffs and handle timeouts wait on it, on the real time if there is none.
+    pub(crate) timer: Option<Arc<dyn Timer>>,
 }
 
 impl ProcStack {
     /// Adds pid for the process which is going to take this stack
     ///
+    /// Processes get a unique pid allocated when they are built, unless it is set here.
+    ///
     /// ```rust
     /// use lightproc::proc_stack::ProcStack;
     ///
@@ -62,6 +137,97 @@ impl ProcStack {
         self
     }
 
+    /// Sets the scheduling priority of the process which is going to take this stack
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::{Priority, ProcStack};
+    ///
+    /// ProcStack::default()
+    ///     .with_priority(Priority::High);
+    /// ```
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Pins the process which is going to take this stack to the given core
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::{CoreId, ProcStack};
+    ///
+    /// ProcStack::default()
+    ///     .with_affinity(CoreId { id: 0 });
+    /// ```
+    pub fn with_affinity(mut self, core: CoreId) -> Self {
+        self.affinity = Some(core);
+        self
+    }
+
+    /// Stores a value for the process which is going to take this stack
+    ///
+    /// Values are stored by their type, so a value replaces the previous one of the same type.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// ProcStack::default()
+    ///     .with_local(String::from("tenant-1"));
+    /// ```
+    pub fn with_local<T>(mut self, value: T) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.locals.insert(value);
+        self
+    }
+
+    /// Makes the process which is going to take this stack inherit the locals of the process
+    /// which builds it, except for the types which are set on this stack
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// ProcStack::default()
+    ///     .with_inherited_locals(true);
+    /// ```
+    pub fn with_inherited_locals(mut self, inherit: bool) -> Self {
+        self.inherit_locals = inherit;
+        self
+    }
+
+    /// Makes the process which is going to take this stack a member of the group of the process
+    /// which builds it, so that it is cancelled as soon as that process completes, panics or
+    /// is cancelled
+    ///
+    /// Processes built from outside of another process aren't affected.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// ProcStack::default()
+    ///     .with_scoped(true);
+    /// ```
+    pub fn with_scoped(mut self, scoped: bool) -> Self {
+        self.scoped = scoped;
+        self
+    }
+
+    /// Measures the time spent polling the process which is going to take this stack
+    ///
+    /// Unless it is enabled, the poll time and the longest poll of the process' metrics
+    /// stay zero.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// ProcStack::default()
+    ///     .with_poll_timing(true);
+    /// ```
+    pub fn with_poll_timing(mut self, poll_timing: bool) -> Self {
+        self.poll_timing = poll_timing;
+        self
+    }
+
     /// Adds a callback that will be executed before polling inner future to the stack
     ///
     /// ```rust
@@ -78,6 +244,47 @@ impl ProcStack {
         self
     }
 
+    /// Adds an async callback whose future will be awaited before the inner future is first
+    /// polled, as a part of the process, to the stack
+    ///
+    /// A panic in the setup is handled like a panic of the inner future, which isn't polled then.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// ProcStack::default()
+    ///     .with_setup(|| async { println!("Setup") });
+    /// ```
+    pub fn with_setup<T, F>(mut self, callback: T) -> Self
+    where
+        T: Fn() -> F + Send + Sync + 'static,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.setup = Some(Arc::new(move || Box::pin(callback())));
+        self
+    }
+
+    /// Adds an async callback whose future will be awaited after the inner future resolves,
+    /// as a part of the process, to the stack
+    ///
+    /// A panic in the teardown is handled like a panic of the inner future. The teardown isn't
+    /// executed if the process panics or is cancelled before that.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// ProcStack::default()
+    ///     .with_teardown(|| async { println!("Teardown") });
+    /// ```
+    pub fn with_teardown<T, F>(mut self, callback: T) -> Self
+    where
+        T: Fn() -> F + Send + Sync + 'static,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.teardown = Some(Arc::new(move || Box::pin(callback())));
+        self
+    }
+
     /// Adds a callback that will be executed after inner future resolves to an output to the stack
     ///
     /// ```rust
@@ -105,11 +312,78 @@ impl ProcStack {
     pub fn with_after_panic<T>(mut self, callback: T) -> Self
     where
         T: Fn() + Send + Sync + 'static,
+    {
+        self.after_panic = Some(Arc::new(move |_: &PanicInfo| callback()));
+        self
+    }
+
+    /// Adds a callback that will be executed after inner future panics to the stack,
+    /// with the information about the panic
+    ///
+    /// It replaces the callback added with [with_after_panic](#method.with_after_panic).
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// ProcStack::default()
+    ///     .with_after_panic_info(|info| { println!("After panic: {}", info) });
+    /// ```
+    pub fn with_after_panic_info<T>(mut self, callback: T) -> Self
+    where
+        T: Fn(&PanicInfo) + Send + Sync + 'static,
     {
         self.after_panic = Some(Arc::new(callback));
         self
     }
 
+    /// Adds a callback that will be executed after the process is cancelled and its inner future
+    /// is dropped, with the reason of the cancellation, to the stack
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// ProcStack::default()
+    ///     .with_after_cancel(|reason| { println!("After cancel: {:?}", reason) });
+    /// ```
+    pub fn with_after_cancel<T>(mut self, callback: T) -> Self
+    where
+        T: Fn(Option<&str>) + Send + Sync + 'static,
+    {
+        self.after_cancel = Some(Arc::new(callback));
+        self
+    }
+
+    /// Sets the clock that the process waits on, for the backoff of its restarts and the
+    /// timeouts of its handle
+    ///
+    /// Executors with a clock of their own, like a virtual one, set it for the processes they
+    /// spawn. Processes wait on the real time otherwise.
+    ///
+    /// ```rust
+    /// use lightproc::prelude::*;
+    /// use std::future::Future;
+    /// use std::pin::Pin;
+    /// use std::time::Duration;
+    ///
+    /// struct Instantly;
+    ///
+    /// impl Timer for Instantly {
+    ///     fn sleep(&self, _: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+    ///         Box::pin(async {})
+    ///     }
+    /// }
+    ///
+    /// ProcStack::default()
+    ///     .with_timer(Instantly);
+    /// ```
+    pub fn with_timer<T>(mut self, timer: T) -> Self
+    where
+        T: Timer + 'static,
+    {
+        self.timer = Some(Arc::new(timer));
+        self
+    }
+
     /// Utility function to get_pid for the implementation of executors.
     ///
     /// ```rust
@@ -122,12 +396,101 @@ impl ProcStack {
     pub fn get_pid(&self) -> usize {
         self.pid.load(Ordering::Acquire)
     }
+
+    /// Utility function to get the pid of the parent process for the implementation of executors.
+    ///
+    /// Parent process is the one which was running on the thread when the process was built,
+    /// `0` if there was none.
+    ///
+    /// ```rust
+    /// use lightproc::prelude::*;
+    ///
+    /// let (proc, _handle) = LightProc::build(async {}, |_| {}, ProcStack::default());
+    ///
+    /// assert_ne!(proc.stack().get_pid(), 0);
+    /// assert_eq!(proc.stack().get_parent_pid(), 0);
+    /// ```
+    pub fn get_parent_pid(&self) -> usize {
+        self.parent_pid
+    }
+
+    /// Utility function to get the scheduling priority for the implementation of executors.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::{Priority, ProcStack};
+    ///
+    /// let proc = ProcStack::default().with_priority(Priority::Low);
+    ///
+    /// assert_eq!(proc.get_priority(), Priority::Low);
+    /// ```
+    pub fn get_priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Utility function to get the core that the process is pinned to
+    /// for the implementation of executors.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::{CoreId, ProcStack};
+    ///
+    /// let proc = ProcStack::default().with_affinity(CoreId { id: 2 });
+    ///
+    /// assert_eq!(proc.get_affinity(), Some(CoreId { id: 2 }));
+    /// ```
+    pub fn get_affinity(&self) -> Option<CoreId> {
+        self.affinity
+    }
+
+    /// Utility function to get the value of the given type stored for the process.
+    ///
+    /// ```rust
+    /// use lightproc::proc_stack::ProcStack;
+    ///
+    /// let proc = ProcStack::default().with_local(7u32);
+    ///
+    /// assert_eq!(proc.get_local::<u32>(), Some(&7));
+    /// assert_eq!(proc.get_local::<u64>(), None);
+    /// ```
+    pub fn get_local<T>(&self) -> Option<&T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.locals.get()
+    }
+}
+
+/// Scheduling priority of a lightweight process
+///
+/// Processes are created with [Priority::Normal] unless stated otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Processes which only run when there is nothing more important to do
+    Low,
+    /// Default priority of the processes
+    #[default]
+    Normal,
+    /// Processes which run before all others, like system processes
+    High,
+}
+
+/// Identifier of a core that processes can be pinned to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CoreId {
+    /// Used core ID
+    pub id: usize,
 }
 
 impl Debug for ProcStack {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
         fmt.debug_struct("ProcStack")
             .field("pid", &self.pid.load(Ordering::SeqCst))
+            .field("parent_pid", &self.parent_pid)
+            .field("priority", &self.priority)
+            .field("affinity", &self.affinity)
+            .field("locals", &self.locals.len())
+            .field("inherit_locals", &self.inherit_locals)
+            .field("scoped", &self.scoped)
+            .field("poll_timing", &self.poll_timing)
             .finish()
     }
 }
@@ -136,9 +499,21 @@ impl Clone for ProcStack {
     fn clone(&self) -> Self {
         ProcStack {
             pid: AtomicUsize::new(self.pid.load(Ordering::Acquire)),
+            parent_pid: self.parent_pid,
+            priority: self.priority,
+            affinity: self.affinity,
+            locals: self.locals.clone(),
+            inherit_locals: self.inherit_locals,
+            scoped: self.scoped,
+            poll_timing: self.poll_timing,
+            group: Arc::default(),
             before_start: self.before_start.clone(),
             after_complete: self.after_complete.clone(),
             after_panic: self.after_panic.clone(),
+            setup: self.setup.clone(),
+            teardown: self.teardown.clone(),
+            after_cancel: self.after_cancel.clone(),
+            timer: self.timer.clone(),
         }
     }
 }
@@ -1,20 +1,27 @@
 use crate::catch_unwind::CatchUnwind;
+use crate::current::{self, Current};
 use crate::layout_helpers::extend;
 use crate::lightproc::LightProc;
+use crate::local;
 use crate::proc_data::ProcData;
+use crate::proc_group::ProcGroup;
 use crate::proc_layout::ProcLayout;
+use crate::proc_metrics::Counters;
 use crate::proc_stack::ProcStack;
 use crate::proc_vtable::ProcVTable;
+use crate::registry;
+use crate::slab;
 use crate::state::*;
-use std::alloc::{self, Layout};
+use std::alloc::Layout;
 use std::cell::Cell;
 use std::future::Future;
 use std::mem::{self, ManuallyDrop};
 use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
 use std::ptr::NonNull;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
 use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::time::Instant;
 
 /// Raw pointers to the fields of a proc.
 pub(crate) struct RawProc<F, R, S> {
@@ -41,13 +48,13 @@ where
     /// Allocates a proc with the given `future` and `schedule` function.
     ///
     /// It is assumed there are initially only the `LightProc` reference and the `ProcHandle`.
-    pub(crate) fn allocate(stack: ProcStack, future: F, schedule: S) -> NonNull<()> {
+    pub(crate) fn allocate(mut stack: ProcStack, future: F, schedule: S) -> NonNull<()> {
         // Compute the layout of the proc for allocation. Abort if the computation fails.
         let proc_layout = Self::proc_layout();
 
         unsafe {
-            // Allocate enough space for the entire proc.
-            let raw_proc = match NonNull::new(alloc::alloc(proc_layout.layout) as *mut ()) {
+            // Allocate enough space for the entire proc, recycling a destroyed one if possible.
+            let raw_proc = match NonNull::new(slab::alloc(proc_layout.layout) as *mut ()) {
                 None => std::process::abort(),
                 Some(p) => p,
             };
@@ -72,9 +79,14 @@ where
                     destroy: Self::destroy,
                     run: Self::run,
                 },
+                reason: AtomicPtr::default(),
+                metrics: Counters::default(),
+                listeners: AtomicPtr::default(),
             });
 
             // Write the stack as the second field of the proc.
+            registry::assign_pid(&mut stack);
+            local::inherit(&mut stack);
             (raw.stack as *mut ProcStack).write(stack);
 
             // Write the schedule function as the third field of the proc.
@@ -83,6 +95,13 @@ where
             // Write the future as the fourth field of the proc.
             raw.future.write(future);
 
+            registry::register(raw_proc.as_ptr());
+
+            // Join the group of the current proc if the stack is scoped.
+            if (*raw.stack).scoped {
+                current::with(|parent| ProcGroup::join(&parent.group, raw_proc));
+            }
+
             raw_proc
         }
     }
@@ -136,6 +155,7 @@ where
     /// Wakes a waker.
     unsafe fn wake(ptr: *const ()) {
         let raw = Self::from_ptr(ptr);
+        (*raw.pdata).metrics.wakeup();
 
         let mut state = (*raw.pdata).state.load(Ordering::Acquire);
 
@@ -197,6 +217,7 @@ where
     /// Wakes a waker by reference.
     unsafe fn wake_by_ref(ptr: *const ()) {
         let raw = Self::from_ptr(ptr);
+        (*raw.pdata).metrics.wakeup();
 
         let mut state = (*raw.pdata).state.load(Ordering::Acquire);
 
@@ -331,6 +352,9 @@ where
         let raw = Self::from_ptr(ptr);
         let proc_layout = Self::proc_layout();
 
+        // Remove the proc from the registry before it goes away.
+        registry::unregister(ptr);
+
         // We need a safeguard against panics because destructors can panic.
         // Drop the schedule function.
         (raw.schedule as *mut S).drop_in_place();
@@ -338,8 +362,12 @@ where
         // Drop the stack.
         (raw.stack as *mut ProcStack).drop_in_place();
 
-        // Finally, deallocate the memory reserved by the proc.
-        alloc::dealloc(ptr as *mut u8, proc_layout.layout);
+        // Drop the reason of the cancellation.
+        (*raw.pdata).drop_reason();
+        (*raw.pdata).drop_listeners();
+
+        // Finally, give the memory reserved by the proc back for reuse.
+        slab::dealloc(ptr as *mut u8, proc_layout.layout);
     }
 
     /// Runs a proc.
@@ -369,6 +397,7 @@ where
 
                 // Drop the future.
                 Self::drop_future(ptr);
+                (*raw.pdata).cancelled();
 
                 // Drop the proc reference.
                 Self::decrement(ptr);
@@ -391,6 +420,10 @@ where
             }
         }
 
+        // Mark the proc as the current one of the thread while it's being polled, so that its
+        // locals can be read and the procs built meanwhile know their parent.
+        let _current = Current::enter(raw.stack);
+
         // Poll the inner future, but surround it with a guard that closes the proc in case polling
         // panics.
         let guard = Guard(raw);
@@ -399,7 +432,15 @@ where
             (*before_start_cb.clone())();
         }
 
+        let started = if (*raw.stack).poll_timing {
+            Some(Instant::now())
+        } else {
+            None
+        };
         let poll = <F as Future>::poll(Pin::new_unchecked(&mut *raw.future), cx);
+        (*raw.pdata)
+            .metrics
+            .poll(started.map(|started| started.elapsed()));
         mem::forget(guard);
 
         match poll {
@@ -444,6 +485,10 @@ where
                                 (*after_complete_cb.clone())();
                             }
 
+                            // Procs of the group don't outlive it.
+                            (*raw.stack).group.close("parent completed");
+                            (*raw.pdata).ended();
+
                             // Drop the proc reference.
                             Self::decrement(ptr);
                             break;
@@ -481,6 +526,7 @@ where
                                 // The thread that closed the proc didn't drop the future because
                                 // it was running so now it's our responsibility to do so.
                                 Self::drop_future(ptr);
+                                (*raw.pdata).cancelled();
 
                                 // Drop the proc reference.
                                 Self::decrement(ptr);
@@ -540,6 +586,7 @@ where
                     // The thread that closed the proc didn't drop the future because it
                     // was running so now it's our responsibility to do so.
                     RawProc::<F, R, S>::drop_future(ptr);
+                    (*raw.stack).group.close("parent panicked");
 
                     // Drop the proc reference.
                     RawProc::<F, R, S>::decrement(ptr);
@@ -556,12 +603,15 @@ where
                     Ok(state) => {
                         // Drop the future because the proc is now closed.
                         RawProc::<F, R, S>::drop_future(ptr);
+                        (*raw.stack).group.close("parent panicked");
 
                         // Notify the awaiter that the proc has been closed.
                         if state & AWAITER != 0 {
                             (*raw.pdata).notify();
                         }
 
+                        (*raw.pdata).ended();
+
                         // Drop the proc reference.
                         RawProc::<F, R, S>::decrement(ptr);
                         break;
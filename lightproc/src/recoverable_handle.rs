@@ -1,18 +1,66 @@
 //!
 //! Handle for recoverable process
+use crate::combinators::{sealed, Cancellable, JoinAll, Select, Timeout};
+use crate::panic_info::PanicInfo;
 use crate::proc_data::ProcData;
 use crate::proc_handle::ProcHandle;
+use crate::proc_metrics::ProcMetrics;
 use crate::proc_stack::ProcStack;
 use std::fmt::{self, Debug, Formatter};
 use std::future::Future;
 use std::pin::Pin;
+use std::ptr::NonNull;
 use std::task::{Context, Poll};
-use std::thread;
+use std::time::Duration;
 
 /// Recoverable handle which encapsulates a standard Proc Handle and contain all panics inside.
 ///
 /// Execution of `after_panic` will be immediate on polling the [RecoverableHandle]'s future.
-pub struct RecoverableHandle<R>(pub(crate) ProcHandle<thread::Result<R>>);
+pub struct RecoverableHandle<R>(pub(crate) ProcHandle<Result<R, PanicInfo>>);
+
+/// Handle of a recoverable process which resolves to the [ProcOutcome] of the process.
+///
+/// Created with [RecoverableHandle::into_outcome].
+///
+/// # Example
+///
+/// ```rust
+/// use lightproc::prelude::*;
+///
+/// let (proc, handle) = LightProc::recoverable(
+///     async { panic!("boom") },
+///     |_| {},
+///     ProcStack::default(),
+/// );
+/// proc.run();
+///
+/// match futures::executor::block_on(handle.into_outcome()) {
+///     ProcOutcome::Panicked(info) => assert_eq!(info.message(), Some("boom")),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub struct OutcomeHandle<R>(RecoverableHandle<R>);
+
+/// How a recoverable process ended.
+#[derive(Debug)]
+pub enum ProcOutcome<R> {
+    /// The process completed with the given output.
+    Completed(R),
+    /// The process panicked.
+    Panicked(PanicInfo),
+    /// The process was cancelled, with the given reason if any.
+    Cancelled(Option<String>),
+}
+
+impl<R> ProcOutcome<R> {
+    /// Returns the output of the process if it completed.
+    pub fn completed(self) -> Option<R> {
+        match self {
+            ProcOutcome::Completed(output) => Some(output),
+            _ => None,
+        }
+    }
+}
 
 impl<R> RecoverableHandle<R> {
     /// Cancels the proc.
@@ -24,6 +72,109 @@ impl<R> RecoverableHandle<R> {
         self.0.cancel()
     }
 
+    /// Cancels the proc with the given reason, which is passed to the `after_cancel` callback.
+    ///
+    /// If the proc has already been cancelled, the first reason is kept.
+    pub fn cancel_with<T>(&self, reason: T)
+    where
+        T: Into<String>,
+    {
+        self.0.cancel_with(reason)
+    }
+
+    /// Returns the poll accounting of the proc so far.
+    pub fn metrics(&self) -> ProcMetrics {
+        self.0.metrics()
+    }
+
+    /// Returns a reference to the stack stored inside the proc.
+    pub fn stack(&self) -> &ProcStack {
+        self.0.stack()
+    }
+
+    /// Lets the proc run to completion on its own, dropping the handle.
+    ///
+    /// This is what dropping the handle does, unless it is in the `abort_on_drop` mode.
+    pub fn detach(self) {
+        self.0.detach()
+    }
+
+    /// Makes dropping the handle cancel the proc, unless it was detached.
+    pub fn abort_on_drop(self) -> Self {
+        RecoverableHandle(self.0.abort_on_drop())
+    }
+
+    /// Resolves to the output of the proc, or cancels it with the "timed out" reason and resolves
+    /// to `None` if it doesn't complete in the given time.
+    pub fn with_timeout(self, timeout: Duration) -> Timeout<Self> {
+        Timeout::new(self, timeout)
+    }
+
+    /// Resolves to the outputs of all the given procs, in their order.
+    pub fn join_all<I>(handles: I) -> JoinAll<Self>
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        JoinAll::new(handles)
+    }
+
+    /// Resolves to the index and the output of the first of the given procs which ends,
+    /// cancelling the other ones with the "lost select" reason.
+    pub fn select<I>(handles: I) -> Select<Self>
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        Select::new(handles)
+    }
+
+    /// Turns this handle into one that resolves to the [ProcOutcome] of the proc,
+    /// telling apart its panics and cancellations.
+    pub fn into_outcome(self) -> OutcomeHandle<R> {
+        OutcomeHandle(self)
+    }
+
+    fn poll_outcome(&mut self, cx: &mut Context) -> Poll<ProcOutcome<R>> {
+        let outcome = match Pin::new(&mut self.0).poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Some(Ok(output))) => ProcOutcome::Completed(output),
+            Poll::Ready(Some(Err(info))) => {
+                if let Some(after_panic_cb) = self.0.stack().after_panic.clone() {
+                    (*after_panic_cb)(&info);
+                }
+
+                ProcOutcome::Panicked(info)
+            }
+            // Recoverable procs only get closed without an output by cancellation.
+            Poll::Ready(None) => ProcOutcome::Cancelled(self.0.cancel_reason().map(String::from)),
+        };
+
+        Poll::Ready(outcome)
+    }
+}
+
+impl<R> OutcomeHandle<R> {
+    /// Cancels the proc.
+    ///
+    /// If the proc has already completed, calling this method will have no effect.
+    pub fn cancel(&self) {
+        self.0.cancel()
+    }
+
+    /// Cancels the proc with the given reason.
+    ///
+    /// If the proc has already been cancelled, the first reason is kept.
+    pub fn cancel_with<T>(&self, reason: T)
+    where
+        T: Into<String>,
+    {
+        self.0.cancel_with(reason)
+    }
+
+    /// Returns the poll accounting of the proc so far.
+    pub fn metrics(&self) -> ProcMetrics {
+        self.0.metrics()
+    }
+
     /// Returns a reference to the stack stored inside the proc.
     pub fn stack(&self) -> &ProcStack {
         self.0.stack()
@@ -34,18 +185,31 @@ impl<R> Future for RecoverableHandle<R> {
     type Output = Option<R>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        match Pin::new(&mut self.0).poll(cx) {
-            Poll::Pending => Poll::Pending,
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Ready(Some(Ok(val))) => Poll::Ready(Some(val)),
-            Poll::Ready(Some(Err(_))) => {
-                if let Some(after_panic_cb) = self.0.stack().after_panic.clone() {
-                    (*after_panic_cb.clone())();
-                }
+        self.poll_outcome(cx).map(ProcOutcome::completed)
+    }
+}
 
-                Poll::Ready(None)
-            }
-        }
+impl<R> Future for OutcomeHandle<R> {
+    type Output = ProcOutcome<R>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.0.poll_outcome(cx)
+    }
+}
+
+impl<R> Cancellable for RecoverableHandle<R> {
+    fn cancel_with(&self, reason: &str) {
+        self.0.cancel_with(reason)
+    }
+}
+
+impl<R> sealed::Handle for RecoverableHandle<R> {
+    fn raw_proc(&self) -> NonNull<()> {
+        self.0.raw_proc
+    }
+
+    fn stack(&self) -> &ProcStack {
+        self.0.stack()
     }
 }
 
@@ -60,3 +224,9 @@ impl<R> Debug for RecoverableHandle<R> {
             .finish()
     }
 }
+
+impl<R> Debug for OutcomeHandle<R> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
@@ -0,0 +1,205 @@
+//!
+//! Process ids and the registry of live processes
+//!
+//! Every process that is built without an explicit pid gets a unique one, allocated in
+//! increasing order, and records the pid of the process that was running on the thread
+//! when it was built as its parent.
+//!
+//! Once [enable]d, the registry keeps track of the live processes, so that they can be listed
+//! with [procs], e.g. to find out what is still running while a shutdown hangs.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lightproc::prelude::*;
+//! use lightproc::registry::{self, ProcState};
+//!
+//! registry::enable();
+//!
+//! let (proc, _handle) = LightProc::build(async {}, |_| {}, ProcStack::default());
+//!
+//! let info = registry::procs()
+//!     .into_iter()
+//!     .find(|info| info.pid == proc.stack().get_pid())
+//!     .unwrap();
+//! assert_eq!(info.state, ProcState::Scheduled);
+//! ```
+use crate::current;
+use crate::proc_data::ProcData;
+use crate::proc_metrics::ProcMetrics;
+use crate::proc_stack::ProcStack;
+use crate::state::*;
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Pid of the next process, pid `0` stands for "no process".
+static NEXT_PID: AtomicUsize = AtomicUsize::new(1);
+
+/// Whether the new processes are registered.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Number of the registered processes, to skip locking while there are none.
+static REGISTERED: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    /// Addresses of the registered processes.
+    static ref PROCS: Mutex<HashSet<usize>> = Mutex::new(HashSet::new());
+}
+
+///
+/// State of a live process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProcState {
+    /// Waiting to be polled.
+    Scheduled,
+    /// Being polled.
+    Running,
+    /// Waiting to be woken up.
+    Idle,
+    /// Completed, its output wasn't taken yet.
+    Completed,
+    /// Cancelled, panicked or its output was taken.
+    Closed,
+}
+
+///
+/// Information about a live process.
+#[derive(Clone, Debug)]
+pub struct ProcInfo {
+    /// Pid of the process.
+    pub pid: usize,
+    /// Pid of the process that built this one, `0` if it was built outside of a process.
+    pub parent_pid: usize,
+    /// State of the process.
+    pub state: ProcState,
+    /// Poll accounting of the process so far.
+    pub metrics: ProcMetrics,
+}
+
+///
+/// Starts registering the processes that are built from now on.
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+///
+/// Stops registering the new processes.
+///
+/// Processes that are already registered stay in the registry until they are gone.
+pub fn disable() {
+    ENABLED.store(false, Ordering::SeqCst);
+}
+
+///
+/// Whether the new processes are registered.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+///
+/// Lists the registered processes that are still alive, ordered by their pids.
+pub fn procs() -> Vec<ProcInfo> {
+    let procs = PROCS.lock().unwrap();
+
+    let mut infos: Vec<ProcInfo> = procs
+        .iter()
+        .map(|&ptr| {
+            // Processes unregister themselves before they are deallocated,
+            // which can't happen while the registry is locked.
+            let pdata = ptr as *const ProcData;
+            let stack = (ptr + ProcData::offset_stack()) as *const ProcStack;
+
+            unsafe {
+                ProcInfo {
+                    pid: (*stack).get_pid(),
+                    parent_pid: (*stack).get_parent_pid(),
+                    state: state_of((*pdata).state.load(Ordering::Acquire)),
+                    metrics: (*pdata).metrics.snapshot(),
+                }
+            }
+        })
+        .collect();
+
+    infos.sort_by_key(|info| info.pid);
+    infos
+}
+
+///
+/// Clones the stacks of the registered processes whose future is still pending, ordered by
+/// their pids.
+pub fn pending_stacks() -> Vec<ProcStack> {
+    let procs = PROCS.lock().unwrap();
+
+    let mut stacks: Vec<ProcStack> = procs
+        .iter()
+        .filter_map(|&ptr| {
+            // Same as in `procs`, the process can't be deallocated while the registry is locked.
+            let pdata = ptr as *const ProcData;
+            let stack = (ptr + ProcData::offset_stack()) as *const ProcStack;
+
+            unsafe {
+                let state = (*pdata).state.load(Ordering::Acquire);
+                if state & (COMPLETED | CLOSED) == 0 {
+                    Some((*stack).clone())
+                } else {
+                    None
+                }
+            }
+        })
+        .collect();
+
+    stacks.sort_by_key(ProcStack::get_pid);
+    stacks
+}
+
+///
+/// Pid of the process that is being polled on the current thread, if any.
+pub fn current_pid() -> Option<usize> {
+    current::with(|stack| stack.get_pid())
+}
+
+fn state_of(state: usize) -> ProcState {
+    if state & CLOSED != 0 {
+        ProcState::Closed
+    } else if state & COMPLETED != 0 {
+        ProcState::Completed
+    } else if state & RUNNING != 0 {
+        ProcState::Running
+    } else if state & SCHEDULED != 0 {
+        ProcState::Scheduled
+    } else {
+        ProcState::Idle
+    }
+}
+
+/// Gives the stack of a new process its parent pid, and a pid unless it already has one.
+pub(crate) fn assign_pid(stack: &mut ProcStack) {
+    if stack.get_pid() == 0 {
+        stack.pid = AtomicUsize::new(NEXT_PID.fetch_add(1, Ordering::Relaxed));
+    }
+    stack.parent_pid = current_pid().unwrap_or(0);
+}
+
+/// Registers a new process if the registry is enabled.
+pub(crate) fn register(ptr: *const ()) {
+    if is_enabled() {
+        let mut procs = PROCS.lock().unwrap();
+        if procs.insert(ptr as usize) {
+            REGISTERED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Unregisters a process that is about to be deallocated.
+pub(crate) fn unregister(ptr: *const ()) {
+    if REGISTERED.load(Ordering::SeqCst) == 0 {
+        return;
+    }
+
+    let mut procs = PROCS.lock().unwrap();
+    if procs.remove(&(ptr as usize)) {
+        REGISTERED.fetch_sub(1, Ordering::SeqCst);
+    }
+}
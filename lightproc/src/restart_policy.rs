@@ -0,0 +1,105 @@
+//!
+//! Policies of the restartable processes
+//!
+//! A restartable process rebuilds its future when it panics, up to the maximum number of restarts
+//! of its [RestartPolicy], waiting for the backoff of the policy before every restart.
+//!
+//! The backoff passes on the [Timer] of the process' stack, so processes of executors with a
+//! virtual clock restart as the virtual time passes.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lightproc::prelude::*;
+//! use std::time::Duration;
+//!
+//! let policy = RestartPolicy::default()
+//!     .with_max_restarts(5)
+//!     .with_exponential_backoff(Duration::from_millis(10), Duration::from_secs(1));
+//!
+//! assert_eq!(policy.backoff(0), Duration::from_millis(10));
+//! assert_eq!(policy.backoff(3), Duration::from_millis(80));
+//! assert_eq!(policy.backoff(10), Duration::from_secs(1));
+//! ```
+//!
+//! [RestartPolicy]: struct.RestartPolicy.html
+//! [Timer]: ../timer/trait.Timer.html
+use crate::delay::Delay;
+use crate::timer::Timer;
+use std::sync::Arc;
+use std::time::Duration;
+
+///
+/// Policy deciding whether and when a restartable process restarts after a panic.
+///
+/// By default a process restarts up to 3 times, right away.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RestartPolicy {
+    max_restarts: usize,
+    backoff: Backoff,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Backoff {
+    None,
+    Fixed(Duration),
+    Exponential { initial: Duration, max: Duration },
+}
+
+impl RestartPolicy {
+    ///
+    /// Sets how many times the process is restarted before it gives up, and resolves with its
+    /// last panic.
+    pub fn with_max_restarts(mut self, max_restarts: usize) -> Self {
+        self.max_restarts = max_restarts;
+        self
+    }
+
+    ///
+    /// Waits the same given time before every restart.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = Backoff::Fixed(backoff);
+        self
+    }
+
+    ///
+    /// Waits the given initial time before the first restart, doubling it for every next
+    /// restart without exceeding the given maximum.
+    pub fn with_exponential_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = Backoff::Exponential { initial, max };
+        self
+    }
+
+    ///
+    /// Maximum number of restarts.
+    pub fn max_restarts(&self) -> usize {
+        self.max_restarts
+    }
+
+    ///
+    /// Time to wait before the given restart, counted from 0.
+    pub fn backoff(&self, restart: usize) -> Duration {
+        match self.backoff {
+            Backoff::None => Duration::from_secs(0),
+            Backoff::Fixed(backoff) => backoff,
+            Backoff::Exponential { initial, max } => {
+                let factor = 1u32.checked_shl(restart as u32).unwrap_or(u32::MAX);
+                initial.checked_mul(factor).unwrap_or(max).min(max)
+            }
+        }
+    }
+
+    /// Future resolving when the process can start the given restart, on the given clock.
+    pub(crate) fn delay(&self, restart: usize, timer: Option<&Arc<dyn Timer>>) -> Delay {
+        Delay::new(self.backoff(restart), timer)
+    }
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            max_restarts: 3,
+            backoff: Backoff::None,
+        }
+    }
+}
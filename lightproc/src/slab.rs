@@ -0,0 +1,160 @@
+//! Per-thread free lists recycling the memory of destroyed processes.
+//!
+//! Process blocks are rounded up to a size class, and a destroyed process gives its block back
+//! to the free list of the thread destroying it, whichever thread allocated it. Blocks of a class
+//! all share the same layout, so any thread can hand them back to the global allocator, which
+//! is NUMA-local when bastion-executor's `unstable` feature selects it.
+use std::alloc::{self, Layout};
+use std::cell::RefCell;
+use std::ptr::NonNull;
+
+/// Alignment of the pooled blocks, more strictly aligned processes bypass the pool.
+const ALIGN: usize = 16;
+
+/// Size of the smallest class, every next class doubles it.
+const MIN_SIZE: usize = 128;
+
+/// Number of size classes, the largest class holds 4 KiB blocks.
+const CLASSES: usize = 6;
+
+/// Number of free blocks a thread keeps per class before returning them to the allocator.
+const MAX_CACHED: usize = 64;
+
+thread_local! {
+    static SLAB: RefCell<Slab> = RefCell::new(Slab::default());
+}
+
+#[derive(Default)]
+struct Slab {
+    free: [Vec<NonNull<u8>>; CLASSES],
+}
+
+impl Drop for Slab {
+    fn drop(&mut self) {
+        for (class, free) in self.free.iter_mut().enumerate() {
+            for block in free.drain(..) {
+                unsafe { alloc::dealloc(block.as_ptr(), class_layout(class)) };
+            }
+        }
+    }
+}
+
+/// Returns the size class serving the given layout, if any.
+fn class_of(layout: Layout) -> Option<usize> {
+    if layout.align() > ALIGN {
+        return None;
+    }
+
+    let size = layout.size().max(MIN_SIZE).next_power_of_two();
+    let class = (size / MIN_SIZE).trailing_zeros() as usize;
+
+    if class < CLASSES {
+        Some(class)
+    } else {
+        None
+    }
+}
+
+fn class_layout(class: usize) -> Layout {
+    unsafe { Layout::from_size_align_unchecked(MIN_SIZE << class, ALIGN) }
+}
+
+/// Allocates a block fitting the given layout, reusing a free one of this thread if possible.
+pub(crate) unsafe fn alloc(layout: Layout) -> *mut u8 {
+    let class = match class_of(layout) {
+        Some(class) => class,
+        None => return alloc::alloc(layout),
+    };
+
+    let cached = SLAB
+        .try_with(|slab| slab.borrow_mut().free[class].pop())
+        .unwrap_or(None);
+
+    match cached {
+        Some(block) => block.as_ptr(),
+        None => alloc::alloc(class_layout(class)),
+    }
+}
+
+/// Gives a block allocated with [`alloc`] for the same layout back to this thread.
+pub(crate) unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+    let class = match class_of(layout) {
+        Some(class) => class,
+        None => return alloc::dealloc(ptr, layout),
+    };
+
+    let block = NonNull::new_unchecked(ptr);
+    let cached = SLAB
+        .try_with(|slab| {
+            let free = &mut slab.borrow_mut().free[class];
+            if free.len() < MAX_CACHED {
+                free.push(block);
+                true
+            } else {
+                false
+            }
+        })
+        .unwrap_or(false);
+
+    if !cached {
+        alloc::dealloc(ptr, class_layout(class));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(class: usize) -> usize {
+        SLAB.with(|slab| slab.borrow().free[class].len())
+    }
+
+    #[test]
+    fn freed_block_reused() {
+        let layout = Layout::from_size_align(200, 8).unwrap();
+
+        unsafe {
+            let block = alloc(layout);
+            dealloc(block, layout);
+
+            // Blocks of the same class are reused, whatever the exact size is.
+            let same = alloc(Layout::from_size_align(256, 16).unwrap());
+            assert_eq!(same, block);
+            dealloc(same, layout);
+        }
+    }
+
+    #[test]
+    fn free_list_overflow() {
+        let layout = Layout::from_size_align(1024, 8).unwrap();
+        let class = class_of(layout).unwrap();
+
+        unsafe {
+            let blocks: Vec<_> = (0..MAX_CACHED + 8).map(|_| alloc(layout)).collect();
+            for block in blocks.iter() {
+                dealloc(*block, layout);
+            }
+
+            // Blocks past the limit go back to the allocator.
+            assert_eq!(cached(class), MAX_CACHED);
+
+            let reused: Vec<_> = (0..MAX_CACHED).map(|_| alloc(layout)).collect();
+            assert_eq!(cached(class), 0);
+            assert!(reused
+                .iter()
+                .all(|block| blocks[..MAX_CACHED].contains(block)));
+
+            for block in reused {
+                dealloc(block, layout);
+            }
+        }
+    }
+
+    #[test]
+    fn oversized_blocks_bypass() {
+        assert_eq!(class_of(Layout::from_size_align(100, 8).unwrap()), Some(0));
+        assert_eq!(class_of(Layout::from_size_align(4096, 8).unwrap()), Some(5));
+        assert_eq!(class_of(Layout::from_size_align(4097, 8).unwrap()), None);
+        assert_eq!(class_of(Layout::from_size_align(64, 32).unwrap()), None);
+    }
+}
@@ -0,0 +1,43 @@
+//!
+//! Clocks that processes wait on
+//!
+//! Processes wait for the backoff of their restarts and for the timeouts of their handles on
+//! the [Timer] of their stack. Without one, they wait on the real time, with a single timer
+//! thread shared by all the processes.
+//!
+//! Executors with their own notion of time, like a virtual clock in tests, give it to the
+//! processes with [ProcStack::with_timer].
+//!
+//! # Example
+//!
+//! ```rust
+//! use lightproc::prelude::*;
+//! use std::future::Future;
+//! use std::pin::Pin;
+//! use std::time::Duration;
+//!
+//! /// A clock on which no time ever needs to pass.
+//! struct Instantly;
+//!
+//! impl Timer for Instantly {
+//!     fn sleep(&self, _: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+//!         Box::pin(async {})
+//!     }
+//! }
+//!
+//! let stack = ProcStack::default().with_timer(Instantly);
+//! ```
+//!
+//! [Timer]: trait.Timer.html
+//! [ProcStack::with_timer]: ../proc_stack/struct.ProcStack.html#method.with_timer
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+///
+/// Clock that processes wait on.
+pub trait Timer: Send + Sync {
+    ///
+    /// Returns a future resolving once the given duration passed on this clock.
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
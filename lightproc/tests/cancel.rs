@@ -0,0 +1,60 @@
+use futures::executor;
+use lightproc::prelude::*;
+use std::sync::{Arc, Mutex};
+
+fn recording_stack() -> (ProcStack, Arc<Mutex<Vec<Option<String>>>>) {
+    let cancelled = Arc::new(Mutex::new(Vec::new()));
+    let cancelled_ = cancelled.clone();
+
+    let stack = ProcStack::default().with_after_cancel(move |reason| {
+        cancelled_.lock().unwrap().push(reason.map(String::from));
+    });
+
+    (stack, cancelled)
+}
+
+#[test]
+fn cancel_with_reason() {
+    let (stack, cancelled) = recording_stack();
+    let (proc, handle) = LightProc::recoverable(async { 1 }, |_| {}, stack);
+
+    handle.cancel_with("killed");
+    handle.cancel_with("killed again");
+    proc.run();
+
+    assert_eq!(*cancelled.lock().unwrap(), vec![Some("killed".to_string())]);
+    match executor::block_on(handle.into_outcome()) {
+        ProcOutcome::Cancelled(reason) => {
+            assert_eq!(reason.as_deref(), Some("killed"))
+        }
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+}
+
+#[test]
+fn cancel_without_reason() {
+    let (stack, cancelled) = recording_stack();
+    let (proc, handle) = LightProc::recoverable(async { 1 }, |_| {}, stack);
+
+    handle.cancel();
+    drop(proc);
+
+    assert_eq!(*cancelled.lock().unwrap(), vec![None]);
+    match executor::block_on(handle.into_outcome()) {
+        ProcOutcome::Cancelled(None) => {}
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+}
+
+#[test]
+fn cancel_after_completion() {
+    let (stack, cancelled) = recording_stack();
+    let (proc, handle) = LightProc::build(async { 1 }, |_| {}, stack);
+
+    proc.run();
+    handle.cancel_with("too late");
+
+    assert!(cancelled.lock().unwrap().is_empty());
+    assert_eq!(handle.cancel_reason(), None);
+    assert_eq!(executor::block_on(handle), Some(1));
+}
@@ -0,0 +1,142 @@
+use crossbeam::channel::{self, Sender};
+use futures::executor;
+use futures::future;
+use lightproc::prelude::*;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+/// A clock on which the time passes right away.
+struct Instantly;
+
+impl Timer for Instantly {
+    fn sleep(&self, _: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(async {})
+    }
+}
+
+/// Builds a proc which never completes, reporting the reason it gets cancelled with.
+fn pending(cancelled: &Sender<String>) -> (LightProc, ProcHandle<usize>) {
+    let cancelled = cancelled.clone();
+    let stack = ProcStack::default().with_after_cancel(move |reason| {
+        cancelled
+            .send(reason.unwrap_or_default().to_string())
+            .unwrap();
+    });
+
+    LightProc::build(future::pending(), |_| {}, stack)
+}
+
+fn ready(output: usize) -> ProcHandle<usize> {
+    let (proc, handle) = LightProc::build(async move { output }, |_| {}, ProcStack::default());
+    proc.run();
+    handle
+}
+
+#[test]
+fn join_all() {
+    let handles = (0..3).map(ready);
+
+    assert_eq!(
+        executor::block_on(ProcHandle::join_all(handles)),
+        vec![Some(0), Some(1), Some(2)]
+    );
+}
+
+#[test]
+fn join_all_pending() {
+    let procs: Vec<_> = (0..3)
+        .map(|output| LightProc::build(async move { output }, |_| {}, ProcStack::default()))
+        .collect();
+    let (procs, handles): (Vec<_>, Vec<_>) = procs.into_iter().unzip();
+
+    let join = ProcHandle::join_all(handles);
+    procs.into_iter().for_each(LightProc::run);
+
+    assert_eq!(executor::block_on(join), vec![Some(0), Some(1), Some(2)]);
+}
+
+#[test]
+fn select_cancels_losers() {
+    let (tx, rx) = channel::unbounded();
+    let (first_proc, first) = pending(&tx);
+    let (third_proc, third) = pending(&tx);
+
+    let handles = vec![first, ready(2), third];
+    assert_eq!(
+        executor::block_on(ProcHandle::select(handles)),
+        (1, Some(2))
+    );
+
+    // The losers are closed right away, so running them only runs their after_cancel.
+    first_proc.run();
+    third_proc.run();
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec!["lost select"; 2]);
+}
+
+#[test]
+fn select_cancels_losers_without_polling() {
+    let (tx, rx) = channel::unbounded();
+    let (loser_proc, loser) = pending(&tx);
+    let (winner_proc, winner) = LightProc::build(async { 1 }, |_| {}, ProcStack::default());
+
+    // The winner cancels the loser as it completes, before the select is polled.
+    let select = ProcHandle::select(vec![loser, winner]);
+    winner_proc.run();
+    loser_proc.run();
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec!["lost select"]);
+
+    assert_eq!(executor::block_on(select), (1, Some(1)));
+}
+
+#[test]
+fn timeout_cancels_proc() {
+    let (tx, rx) = channel::unbounded();
+    let (proc, handle) = pending(&tx);
+    let start = Instant::now();
+
+    let timeout = handle.with_timeout(Duration::from_millis(20));
+    assert_eq!(executor::block_on(timeout), None);
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    proc.run();
+    assert_eq!(rx.try_recv().unwrap(), "timed out");
+}
+
+#[test]
+fn timeout_on_stack_timer() {
+    let (proc, handle) = LightProc::build(
+        future::pending::<usize>(),
+        |_| {},
+        ProcStack::default().with_timer(Instantly),
+    );
+
+    let timeout = handle.with_timeout(Duration::from_secs(3600));
+    assert_eq!(executor::block_on(timeout), None);
+    drop(proc);
+}
+
+#[test]
+fn timeout_with_output() {
+    let handle = ready(1);
+
+    assert_eq!(
+        executor::block_on(handle.with_timeout(Duration::from_secs(60))),
+        Some(1)
+    );
+}
+
+#[test]
+fn abort_on_drop() {
+    let (tx, rx) = channel::unbounded();
+    let (aborted_proc, aborted) = pending(&tx);
+    let (detached_proc, detached) = pending(&tx);
+
+    detached.abort_on_drop().detach();
+    detached_proc.run();
+    assert!(rx.try_recv().is_err());
+
+    drop(aborted.abort_on_drop());
+    aborted_proc.run();
+    assert_eq!(rx.try_recv().unwrap(), "");
+}
@@ -0,0 +1,105 @@
+use crossbeam::channel::{self, Receiver};
+use futures::executor;
+use futures::future;
+use lightproc::prelude::*;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+type Child = (LightProc, ProcHandle<()>);
+
+struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Builds a parent proc which builds a child proc with the given stack, that is never run.
+fn parent_with_child<F>(
+    parent: F,
+    child_stack: ProcStack,
+) -> (LightProc, RecoverableHandle<()>, Receiver<Child>)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (tx, rx) = channel::unbounded();
+
+    let (proc, handle) = LightProc::recoverable(
+        async move {
+            tx.send(LightProc::build(future::pending(), |_| {}, child_stack))
+                .unwrap();
+
+            parent.await
+        },
+        |_| {},
+        ProcStack::default(),
+    );
+
+    (proc, handle, rx)
+}
+
+#[test]
+fn scoped_child_cancelled_on_completion() {
+    let (parent, _, rx) = parent_with_child(async {}, ProcStack::default().with_scoped(true));
+    parent.run();
+
+    let (_child, handle) = rx.recv().unwrap();
+    assert_eq!(handle.cancel_reason(), Some("parent completed"));
+    assert_eq!(executor::block_on(handle), None);
+}
+
+#[test]
+fn scoped_child_cancelled_on_panic() {
+    let (parent, _, rx) = parent_with_child(
+        async { panic!("parent failed") },
+        ProcStack::default().with_scoped(true),
+    );
+    parent.run();
+
+    let (_child, handle) = rx.recv().unwrap();
+    assert_eq!(handle.cancel_reason(), Some("parent panicked"));
+}
+
+#[test]
+fn scoped_child_cancelled_with_parent() {
+    let (parent, parent_handle, rx) =
+        parent_with_child(future::pending(), ProcStack::default().with_scoped(true));
+    parent.run();
+
+    let (_child, handle) = rx.recv().unwrap();
+    assert_eq!(handle.cancel_reason(), None);
+
+    // The parent gets rescheduled and dropped, which drops its future.
+    parent_handle.cancel_with("killed");
+    assert_eq!(handle.cancel_reason(), Some("parent cancelled"));
+}
+
+#[test]
+fn unscoped_child_outlives_parent() {
+    let (parent, _, rx) = parent_with_child(async {}, ProcStack::default());
+    parent.run();
+
+    let (_child, handle) = rx.recv().unwrap();
+    assert_eq!(handle.cancel_reason(), None);
+}
+
+#[test]
+fn ended_scoped_child_released() {
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let stack = ProcStack::default()
+        .with_scoped(true)
+        .with_local(DropCounter(dropped.clone()));
+    let (parent, _parent_handle, rx) = parent_with_child(future::pending(), stack);
+    parent.run();
+
+    // The child ends and all of its references are dropped, while the parent keeps running.
+    let (child, handle) = rx.recv().unwrap();
+    handle.cancel();
+    drop(child);
+    drop(handle);
+
+    // Dropping the locals of its stack shows that the child was deallocated.
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+}
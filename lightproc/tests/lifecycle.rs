@@ -0,0 +1,90 @@
+use crossbeam::channel;
+use futures::executor;
+use lightproc::prelude::*;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// Yields once before completing.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+fn recorder() -> (
+    Arc<Mutex<Vec<&'static str>>>,
+    impl Fn(&'static str) + Clone + Send + Sync + 'static,
+) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events_ = events.clone();
+
+    (events, move |event| events_.lock().unwrap().push(event))
+}
+
+#[test]
+fn async_setup_and_teardown() {
+    let (events, record) = recorder();
+    let (setup, body, teardown) = (record.clone(), record.clone(), record);
+
+    let (tx, rx) = channel::unbounded();
+    let (proc, handle) = LightProc::build(
+        async move {
+            body("body");
+            1
+        },
+        move |proc| tx.send(proc).unwrap(),
+        ProcStack::default()
+            .with_setup(move || {
+                let setup = setup.clone();
+                async move {
+                    setup("setup started");
+                    YieldNow(false).await;
+                    setup("setup finished");
+                }
+            })
+            .with_teardown(move || {
+                let teardown = teardown.clone();
+                async move { teardown("teardown") }
+            }),
+    );
+
+    proc.run();
+    assert_eq!(*events.lock().unwrap(), vec!["setup started"]);
+
+    rx.recv().unwrap().run();
+    assert_eq!(executor::block_on(handle), Some(1));
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec!["setup started", "setup finished", "body", "teardown"]
+    );
+}
+
+#[test]
+fn panicking_setup() {
+    let (events, record) = recorder();
+
+    let (proc, handle) = LightProc::recoverable(
+        async move { record("body") },
+        |_| {},
+        ProcStack::default().with_setup(|| async { panic!("setup failed") }),
+    );
+    proc.run();
+
+    match executor::block_on(handle.into_outcome()) {
+        ProcOutcome::Panicked(info) => assert_eq!(info.message(), Some("setup failed")),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+    assert!(events.lock().unwrap().is_empty());
+}
@@ -0,0 +1,68 @@
+use crossbeam::channel;
+use lightproc::local;
+use lightproc::prelude::*;
+
+#[derive(Clone, Debug, PartialEq)]
+struct TraceId(u64);
+
+#[derive(Clone, Debug, PartialEq)]
+struct TenantId(&'static str);
+
+#[test]
+fn locals_outside_of_procs() {
+    assert_eq!(local::get::<TraceId>(), None);
+}
+
+#[test]
+fn locals() {
+    let stack = ProcStack::default()
+        .with_local(TraceId(1))
+        .with_local(TraceId(2))
+        .with_local(TenantId("tenant"));
+    assert_eq!(stack.get_local::<TraceId>(), Some(&TraceId(2)));
+
+    let (proc, handle) = LightProc::build(
+        async {
+            (
+                local::get::<TraceId>(),
+                local::with(|tenant: &TenantId| tenant.0.len()),
+                local::get::<u32>(),
+            )
+        },
+        |_| {},
+        stack,
+    );
+    proc.run();
+
+    assert_eq!(
+        futures::executor::block_on(handle),
+        Some((Some(TraceId(2)), Some(6), None))
+    );
+}
+
+#[test]
+fn inherited_locals() {
+    let (tx, rx) = channel::unbounded();
+
+    let (parent, _) = LightProc::build(
+        async move {
+            let inheriting = ProcStack::default()
+                .with_inherited_locals(true)
+                .with_local(TenantId("child"));
+            let (inheriting, _) = LightProc::build(async {}, |_| {}, inheriting);
+            let (isolated, _) = LightProc::build(async {}, |_| {}, ProcStack::default());
+
+            tx.send((inheriting, isolated)).unwrap();
+        },
+        |_| {},
+        ProcStack::default()
+            .with_local(TraceId(7))
+            .with_local(TenantId("parent")),
+    );
+    parent.run();
+
+    let (inheriting, isolated) = rx.recv().unwrap();
+    assert_eq!(inheriting.stack().get_local(), Some(&TraceId(7)));
+    assert_eq!(inheriting.stack().get_local(), Some(&TenantId("child")));
+    assert_eq!(isolated.stack().get_local::<TraceId>(), None);
+}
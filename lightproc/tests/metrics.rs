@@ -0,0 +1,81 @@
+use crossbeam::channel;
+use futures::executor;
+use lightproc::prelude::*;
+use lightproc::registry;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+/// Yields once, sleeping for the given time in every poll.
+struct SlowYield(Duration, bool);
+
+impl Future for SlowYield {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        thread::sleep(self.0);
+
+        if self.1 {
+            Poll::Ready(())
+        } else {
+            self.1 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[test]
+fn poll_accounting() {
+    registry::enable();
+
+    let (tx, rx) = channel::unbounded();
+    let (proc, handle) = LightProc::recoverable(
+        SlowYield(Duration::from_millis(5), false),
+        move |proc| tx.send(proc).unwrap(),
+        ProcStack::default().with_poll_timing(true),
+    );
+    let pid = proc.stack().get_pid();
+    assert_eq!(proc.metrics(), ProcMetrics::default());
+
+    proc.run();
+    let proc = rx.recv().unwrap();
+    assert_eq!(proc.metrics().polls(), 1);
+    assert_eq!(proc.metrics().wakeups(), 1);
+
+    let info = registry::procs()
+        .into_iter()
+        .find(|info| info.pid == pid)
+        .unwrap();
+    assert_eq!(info.metrics.polls(), 1);
+
+    proc.run();
+    let metrics = handle.metrics();
+    assert_eq!(metrics.polls(), 2);
+    assert_eq!(metrics.wakeups(), 1);
+    assert!(metrics.poll_time() >= Duration::from_millis(10));
+    assert!(metrics.longest_poll() >= Duration::from_millis(5));
+    assert!(metrics.longest_poll() <= metrics.poll_time());
+
+    assert_eq!(executor::block_on(handle), Some(()));
+}
+
+#[test]
+fn poll_timing_is_opt_in() {
+    let (tx, rx) = channel::unbounded();
+    let (proc, handle) = LightProc::recoverable(
+        SlowYield(Duration::from_millis(5), false),
+        move |proc| tx.send(proc).unwrap(),
+        ProcStack::default(),
+    );
+
+    proc.run();
+    rx.recv().unwrap().run();
+
+    let metrics = handle.metrics();
+    assert_eq!(metrics.polls(), 2);
+    assert_eq!(metrics.poll_time(), Duration::default());
+    assert_eq!(metrics.longest_poll(), Duration::default());
+}
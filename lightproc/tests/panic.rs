@@ -0,0 +1,54 @@
+use futures::executor;
+use lightproc::prelude::*;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn panic_info() {
+    let reported = Arc::new(Mutex::new(None));
+    let reported_ = reported.clone();
+
+    let (proc, handle) = LightProc::recoverable(
+        async { panic!("failed with {}", 42) },
+        |_| {},
+        ProcStack::default().with_after_panic_info(move |info| {
+            *reported_.lock().unwrap() = info.message().map(str::to_string);
+        }),
+    );
+    proc.run();
+
+    let info = match executor::block_on(handle.into_outcome()) {
+        ProcOutcome::Panicked(info) => info,
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    };
+    assert_eq!(info.message(), Some("failed with 42"));
+    assert_eq!(info.location().unwrap().file(), file!());
+    assert_eq!(reported.lock().unwrap().as_deref(), Some("failed with 42"));
+}
+
+#[test]
+fn panic_payload() {
+    let (proc, handle) = LightProc::recoverable(
+        async { std::panic::resume_unwind(Box::new(7usize)) },
+        |_| {},
+        ProcStack::default(),
+    );
+    proc.run();
+
+    let info = match executor::block_on(handle.into_outcome()) {
+        ProcOutcome::Panicked(info) => info,
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    };
+    assert_eq!(info.message(), None);
+    assert_eq!(info.into_payload().downcast_ref::<usize>(), Some(&7));
+}
+
+#[test]
+fn completed_result() {
+    let (proc, handle) = LightProc::recoverable(async { 1 }, |_| {}, ProcStack::default());
+    proc.run();
+
+    assert_eq!(
+        executor::block_on(handle.into_outcome()).completed(),
+        Some(1)
+    );
+}
@@ -0,0 +1,76 @@
+use crossbeam::channel;
+use lightproc::prelude::*;
+use lightproc::registry::{self, ProcState};
+
+#[test]
+fn unique_pids() {
+    let (first, _) = LightProc::build(async {}, |_| {}, ProcStack::default());
+    let (second, _) = LightProc::build(async {}, |_| {}, ProcStack::default());
+    let (explicit, _) = LightProc::build(async {}, |_| {}, ProcStack::default().with_pid(12));
+
+    assert_ne!(first.stack().get_pid(), 0);
+    assert!(second.stack().get_pid() > first.stack().get_pid());
+    assert_eq!(explicit.stack().get_pid(), 12);
+}
+
+#[test]
+fn parent_pids() {
+    let (tx, rx) = channel::unbounded();
+
+    let (parent, _) = LightProc::build(
+        async move {
+            let (child, _) = LightProc::build(async {}, |_| {}, ProcStack::default());
+            tx.send(child).unwrap();
+        },
+        |_| {},
+        ProcStack::default(),
+    );
+    let parent_pid = parent.stack().get_pid();
+    assert_eq!(parent.stack().get_parent_pid(), 0);
+
+    parent.run();
+
+    let child = rx.recv().unwrap();
+    assert_eq!(child.stack().get_parent_pid(), parent_pid);
+}
+
+#[test]
+fn live_procs() {
+    registry::enable();
+
+    let (proc, handle) = LightProc::build(async { 1 }, |_| {}, ProcStack::default());
+    let pid = proc.stack().get_pid();
+    let state_of = |pid| {
+        registry::procs()
+            .into_iter()
+            .find(|info| info.pid == pid)
+            .map(|info| info.state)
+    };
+
+    assert_eq!(state_of(pid), Some(ProcState::Scheduled));
+
+    proc.run();
+    assert_eq!(state_of(pid), Some(ProcState::Completed));
+
+    drop(handle);
+    assert_eq!(state_of(pid), None);
+}
+
+#[test]
+fn pending_stacks() {
+    registry::enable();
+
+    let (proc, handle) = LightProc::build(async { 1 }, |_| {}, ProcStack::default());
+    let pid = proc.stack().get_pid();
+    let is_pending = |pid| {
+        registry::pending_stacks()
+            .iter()
+            .any(|stack| stack.get_pid() == pid)
+    };
+
+    assert!(is_pending(pid));
+
+    proc.run();
+    assert!(!is_pending(pid));
+    drop(handle);
+}
@@ -0,0 +1,74 @@
+use crossbeam::channel;
+use futures::executor;
+use lightproc::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Builds a restartable proc panicking on its attempts before the given one, and runs it on a
+/// thread until it resolves.
+fn failing_until(
+    succeeding_attempt: usize,
+    policy: RestartPolicy,
+) -> (OutcomeHandle<usize>, Arc<AtomicUsize>) {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let attempts_ = attempts.clone();
+
+    let (tx, rx) = channel::unbounded();
+    let (proc, handle) = LightProc::restartable(
+        move || {
+            let attempt = attempts_.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt < succeeding_attempt {
+                    panic!("attempt {} failed", attempt);
+                }
+                attempt
+            }
+        },
+        move |proc| tx.send(proc).unwrap(),
+        ProcStack::default(),
+        policy,
+    );
+
+    proc.schedule();
+    thread::spawn(move || {
+        for proc in rx {
+            proc.run();
+        }
+    });
+
+    (handle.into_outcome(), attempts)
+}
+
+#[test]
+fn restarted_until_completed() {
+    let (handle, attempts) = failing_until(2, RestartPolicy::default());
+
+    assert_eq!(executor::block_on(handle).completed(), Some(2));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn gives_up_after_max_restarts() {
+    let (handle, attempts) =
+        failing_until(usize::MAX, RestartPolicy::default().with_max_restarts(2));
+
+    match executor::block_on(handle) {
+        ProcOutcome::Panicked(info) => assert_eq!(info.message(), Some("attempt 2 failed")),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn waits_for_backoff() {
+    let start = Instant::now();
+    let (handle, _) = failing_until(
+        2,
+        RestartPolicy::default().with_backoff(Duration::from_millis(20)),
+    );
+
+    assert_eq!(executor::block_on(handle).completed(), Some(2));
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}
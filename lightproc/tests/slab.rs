@@ -0,0 +1,51 @@
+use crossbeam::channel;
+use futures::executor;
+use lightproc::prelude::*;
+use std::thread;
+
+/// Builds a proc whose future holds `$size` bytes, to cover the pooled and the oversized blocks.
+macro_rules! build {
+    ($size:expr, $i:expr) => {{
+        let buf = [$i as u8; $size];
+        LightProc::build(
+            async move { buf.iter().map(|b| *b as usize).sum::<usize>() / $size },
+            |_| {},
+            ProcStack::default(),
+        )
+    }};
+}
+
+#[test]
+fn reused_blocks() {
+    for i in 0..1_000 {
+        let (small, small_handle) = build!(8, i % 256);
+        let (large, large_handle) = build!(8192, i % 256);
+        small.run();
+        large.run();
+
+        assert_eq!(executor::block_on(small_handle), Some(i % 256));
+        assert_eq!(executor::block_on(large_handle), Some(i % 256));
+    }
+}
+
+#[test]
+fn blocks_freed_on_other_threads() {
+    let (tx, rx) = channel::unbounded();
+
+    let producer = thread::spawn(move || {
+        for i in 0..1_000 {
+            tx.send(build!(512, i % 256)).unwrap();
+        }
+    });
+
+    // The procs get destroyed, and their blocks recycled, on the consuming thread.
+    let consumer = thread::spawn(move || {
+        for (i, (proc, handle)) in rx.iter().enumerate() {
+            proc.run();
+            assert_eq!(executor::block_on(handle), Some(i % 256));
+        }
+    });
+
+    producer.join().unwrap();
+    consumer.join().unwrap();
+}